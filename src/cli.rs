@@ -0,0 +1,141 @@
+use crate::error::InstallError;
+use crate::install::{self, Installer};
+use crate::platform::Platform;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zed_extension_api::{self as zed, serde_json::Value};
+
+const CLI_NAME: &str = "harper-cli";
+pub const REPORT_FILE: &str = "harper-report.txt";
+
+/// File types linted by a project-wide run.
+const PROSE_GLOBS: &[&str] = &["*.md", "*.markdown", "*.txt", "*.rst", "*.adoc", "*.typ"];
+
+/// Installs the harper-cli published with `version` (the latest release when `None`)
+/// next to the managed harper-ls installs, returning its absolute path.
+pub fn install(
+    installer: &dyn Installer,
+    repo: &str,
+    platform: Platform,
+    version: Option<&str>,
+) -> Result<PathBuf, InstallError> {
+    let release = installer.release(repo, version)?;
+
+    let target = platform.asset_target();
+    let asset = target
+        .find_asset(CLI_NAME, &release.assets)
+        .ok_or_else(|| InstallError::AssetNotFound {
+            target: format!("{CLI_NAME} {}", target.triple()),
+        })?;
+
+    let version_dir = target.version_dir(CLI_NAME, &release.version);
+    let binary_path = PathBuf::from(&version_dir).join(platform.executable_name(CLI_NAME));
+
+    if !binary_path.exists() {
+        install::install_staged(
+            installer,
+            std::slice::from_ref(asset),
+            &release.version,
+            &version_dir,
+            &platform.executable_name(CLI_NAME),
+            |binary| {
+                target
+                    .check_binary(binary)
+                    .map_err(|found| InstallError::WrongTarget {
+                        asset: asset.name.clone(),
+                        expected: target.triple(),
+                        found,
+                    })
+            },
+        )?;
+
+        for entry in fs::read_dir(".").into_iter().flatten().flatten() {
+            if let Ok(name) = entry.file_name().into_string()
+                && name.starts_with(&format!("{CLI_NAME}-"))
+                && name != version_dir
+            {
+                fs::remove_dir_all(entry.path()).ok();
+            }
+        }
+    }
+
+    // The working directory is the extension's directory on the host.
+    Ok(std::env::current_dir()
+        .map(|dir| dir.join(&binary_path))
+        .unwrap_or(binary_path))
+}
+
+/// Translates the server section of the workspace configuration into harper-cli
+/// `lint` flags. Only `dialect` is carried over, as `--dialect`; linter toggles,
+/// dictionaries and the other settings are not.
+pub fn lint_args(section: &Value) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(dialect) = section.get("dialect").and_then(Value::as_str) {
+        args.extend(["--dialect".to_string(), dialect.to_string()]);
+    }
+    args
+}
+
+/// Returns a shell command that lints every tracked prose file of the worktree with
+/// harper-cli and writes the findings to [`REPORT_FILE`] in the worktree root.
+pub fn lint_command(
+    platform: Platform,
+    cli: &Path,
+    worktree_root: &str,
+    lint_args: &[String],
+) -> String {
+    let globs = prose_globs();
+    let quote = |arg: &str| quote(platform.os, arg);
+    let cli = quote(&cli.to_string_lossy());
+    let worktree_root = quote(worktree_root);
+    let lint_args = lint_args
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match platform.os {
+        zed::Os::Windows => format!(
+            "cd {worktree_root}; git ls-files {globs} | ForEach-Object {{ & {cli} lint {lint_args} $_ }} *> {REPORT_FILE}"
+        ),
+        _ => format!(
+            "cd {worktree_root} && git ls-files -z {globs} | xargs -0 -n1 {cli} lint {lint_args} > {REPORT_FILE} 2>&1"
+        ),
+    }
+}
+
+/// Quotes an argument for PowerShell on Windows and POSIX shells elsewhere, so paths
+/// with spaces, quotes or non-ASCII characters survive.
+fn quote(os: zed::Os, arg: &str) -> String {
+    match os {
+        zed::Os::Windows => format!("'{}'", arg.replace('\'', "''")),
+        _ => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+/// Returns a pre-commit configuration and an equivalent husky hook that lint staged
+/// prose files with the harper-cli on `PATH`.
+pub fn hooks(lint_args: &[String]) -> (String, String) {
+    let entry = std::iter::once(format!("{CLI_NAME} lint"))
+        .chain(lint_args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let pre_commit = format!(
+        "repos:\n  - repo: local\n    hooks:\n      - id: harper\n        name: harper\n        entry: {entry}\n        language: system\n        types_or: [markdown, text, rst, asciidoc]\n"
+    );
+    let husky = format!(
+        "git diff --cached --name-only -z --diff-filter=ACM -- {} | xargs -0 -r -n1 {entry}\n",
+        prose_globs()
+    );
+
+    (pre_commit, husky)
+}
+
+fn prose_globs() -> String {
+    PROSE_GLOBS
+        .iter()
+        .map(|glob| format!("'{glob}'"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,635 @@
+use crate::error::InstallError;
+use crate::platform::{self, Platform};
+use crate::stats::{self, TextStats};
+use crate::{
+    HarperExtension, NAME, cli, config, dictionary, failures, installed_versions, is_version_dir,
+    report, schema, server_log,
+};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zed_extension_api::{
+    self as zed, Result, SlashCommandArgumentCompletion, SlashCommandOutput,
+    SlashCommandOutputSection,
+    serde_json::{self, Value},
+};
+
+const SUBCOMMANDS: &[(&str, &str)] = &[
+    (
+        "check",
+        "Check that harper-ls resolves and that settings leave linting enabled",
+    ),
+    (
+        "diagnose",
+        "Report platform, release assets and installed versions",
+    ),
+    (
+        "export",
+        "Print the worktree's Harper settings with portable paths, for sharing",
+    ),
+    (
+        "schema",
+        "Print a JSON schema for completion of lsp.harper-ls settings",
+    ),
+    (
+        "hook",
+        "Print pre-commit and husky hooks that run harper-cli like the editor",
+    ),
+    (
+        "stats",
+        "Word count, sentence length and readability of the given files",
+    ),
+    (
+        "dictionary",
+        "List project words from dependency manifests and source identifiers",
+    ),
+    (
+        "dry-run",
+        "Show what an install would download and which binary would be launched",
+    ),
+    (
+        "lint",
+        "Install harper-cli and print the command that lints the whole project",
+    ),
+    (
+        "report",
+        "Render the findings of `/harper lint` as Markdown or HTML, grouped by rule",
+    ),
+    (
+        "pause",
+        "Silence Harper in this worktree until resumed or Zed restarts",
+    ),
+    ("resume", "Turn Harper back on after `/harper pause`"),
+    (
+        "reset-crashes",
+        "Forget recorded crashes, leaving safe mode and the fallback version",
+    ),
+];
+
+pub fn complete(args: &[String]) -> Vec<SlashCommandArgumentCompletion> {
+    let query = args.first().map(String::as_str).unwrap_or_default();
+
+    SUBCOMMANDS
+        .iter()
+        .filter(|(name, _)| name.starts_with(query))
+        .map(|(name, description)| SlashCommandArgumentCompletion {
+            label: format!("{name} — {description}"),
+            new_text: name.to_string(),
+            run_command: true,
+        })
+        .collect()
+}
+
+pub fn run(
+    extension: &HarperExtension,
+    args: &[String],
+    worktree: Option<&zed::Worktree>,
+) -> Result<SlashCommandOutput> {
+    let worktree = worktree.ok_or("The harper command requires an open worktree")?;
+
+    let (label, text) = match args.first().map(String::as_str) {
+        Some("check") => ("Harper check", check(extension, worktree)?),
+        Some("diagnose") => ("Harper installation report", diagnose(extension, worktree)?),
+        Some("dry-run") => ("Harper install dry run", dry_run(extension, worktree)?),
+        Some("lint") => ("Harper project lint", lint(extension, worktree)?),
+        Some("export") => ("Harper configuration", export(worktree)?),
+        Some("schema") => ("Harper settings schema", schema()?),
+        Some("hook") => ("Harper commit hooks", hook(worktree)),
+        Some("stats") => ("Harper writing statistics", stats(worktree, &args[1..])?),
+        Some("dictionary") => ("Harper dictionary words", dictionary(worktree, &args[1..])?),
+        Some("report") => ("Harper lint report", report(worktree, &args[1..])?),
+        Some("pause") => ("Harper paused", set_paused(extension, worktree, true)),
+        Some("resume") => ("Harper resumed", set_paused(extension, worktree, false)),
+        Some("reset-crashes") => ("Harper crash count", reset_crashes(extension, worktree)),
+        Some(subcommand) => return Err(format!("Unknown harper subcommand: {subcommand}")),
+        None => return Err("Missing harper subcommand, e.g. `/harper check`".to_string()),
+    };
+
+    Ok(SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label: label.to_string(),
+        }],
+        text,
+    })
+}
+
+/// Pauses or resumes linting for the worktree. Only the extension's in-memory state
+/// changes; Zed asks for the configuration again when the server restarts.
+fn set_paused(extension: &HarperExtension, worktree: &zed::Worktree, paused: bool) -> String {
+    extension.set_paused(worktree, paused);
+    let state = if paused {
+        "Harper is paused for this worktree; `/harper resume` turns it back on, as does restarting Zed"
+    } else {
+        "Harper is resumed for this worktree"
+    };
+    format!("{state}. Run `editor: restart language server` to apply it.")
+}
+
+fn reset_crashes(extension: &HarperExtension, worktree: &zed::Worktree) -> String {
+    if extension.reset_crashes(worktree) {
+        "Recorded crashes cleared. Run `editor: restart language server` to leave safe mode and use the current version again.".to_string()
+    } else {
+        "No crashes are recorded for this worktree.".to_string()
+    }
+}
+
+/// Separates "the server cannot start" from "the settings switched every rule off".
+///
+/// The extension API cannot spawn processes, so no text is linted here; both halves of
+/// the question are answered from what the extension controls.
+fn check(extension: &HarperExtension, worktree: &zed::Worktree) -> Result<String> {
+    let mut report = String::new();
+
+    let options = config::options(worktree);
+    let binary = match extension.find_binary(worktree, &options)? {
+        Some(binary) => Some(binary.path),
+        None => installed_versions(Platform::for_worktree(worktree))
+            .into_iter()
+            .next()
+            .map(|(_, path)| path),
+    };
+    let server_ok = match &binary {
+        Some(path) => {
+            report.push_str(&format!("Server: {NAME} resolves to {}\n", path.display()));
+            true
+        }
+        None => {
+            report.push_str(&format!(
+                "Server: {NAME} is not installed yet; it is downloaded when the language server first starts\n"
+            ));
+            false
+        }
+    };
+
+    let settings = server_section(worktree);
+    let excludes_everything = settings
+        .get("excludePatterns")
+        .and_then(Value::as_array)
+        .is_some_and(|patterns| {
+            patterns
+                .iter()
+                .filter_map(Value::as_str)
+                .any(matches_everything)
+        });
+
+    let disabled: Vec<&str> = settings
+        .get("linters")
+        .and_then(Value::as_object)
+        .map(|linters| {
+            linters
+                .iter()
+                .filter(|(_, enabled)| **enabled == Value::Bool(false))
+                .map(|(name, _)| name.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if disabled.is_empty() {
+        report.push_str("Settings: no linters are disabled\n");
+    } else {
+        report.push_str(&format!(
+            "Settings: {} linter(s) disabled: {}\n",
+            disabled.len(),
+            disabled.join(", ")
+        ));
+    }
+
+    for notice in extension.take_notices(worktree) {
+        report.push_str(&format!("Notice: {notice}\n"));
+    }
+
+    let silenced = if !options.enabled {
+        Some("`enabled` is `false`, so no language server is started for this worktree")
+    } else if extension.is_paused(worktree) {
+        Some("Harper is paused for this worktree; run `/harper resume` to turn it back on")
+    } else if excludes_everything {
+        Some("an `exclude` pattern matches every file")
+    } else {
+        None
+    };
+
+    let verdict = match (server_ok, silenced, disabled.is_empty()) {
+        (_, Some(reason), _) => format!("Linting is off: {reason}"),
+        (true, None, true) => {
+            "The server resolves and settings leave linting enabled. No text was linted, as extensions cannot run the server; if diagnostics are missing, look at the server itself".to_string()
+        }
+        (true, None, false) => {
+            "The server resolves; if expected diagnostics are missing, check the disabled linters above".to_string()
+        }
+        (false, None, _) => {
+            "The server is not available yet; restart the language server to install it".to_string()
+        }
+    };
+    report.push_str(&verdict);
+
+    Ok(report)
+}
+
+/// Recognizes globs such as `**`, `**/*` or `./**` that exclude the whole worktree.
+fn matches_everything(pattern: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("./");
+    pattern.contains("**") && pattern.chars().all(|c| matches!(c, '*' | '/'))
+}
+
+fn diagnose(extension: &HarperExtension, worktree: &zed::Worktree) -> Result<String> {
+    let mut report = String::new();
+
+    let current = Platform::current();
+    let platform = Platform::for_worktree(worktree);
+    report.push_str(&format!(
+        "Platform: extension {:?} / {:?}, worktree host {:?} / {:?}\n",
+        current.os, current.arch, platform.os, platform.arch
+    ));
+
+    let target = platform.asset_target();
+    report.push_str(&format!("Expected asset: {}\n", target.asset_name()));
+
+    let options = config::options(worktree);
+    match extension.find_binary(worktree, &options)? {
+        Some(binary) => report.push_str(&format!(
+            "Resolution: {} -> {} (version {})\n",
+            binary.source.describe(),
+            binary.path.display(),
+            binary.version.as_deref().unwrap_or("unknown")
+        )),
+        None => report.push_str("Resolution: extension-managed install (download on next start)\n"),
+    }
+
+    let repo = config::release_repo(&options);
+    match extension.installer.preview_release(&repo, None) {
+        Ok(release) => {
+            report.push_str(&format!("Latest release: {repo} {}\n", release.version));
+            let chosen = target.find_asset(NAME, &release.assets);
+            for asset in &release.assets {
+                let marker = if chosen.is_some_and(|chosen| chosen.name == asset.name) {
+                    " (matches)"
+                } else {
+                    ""
+                };
+                report.push_str(&format!("  - {}{marker}\n", asset.name));
+            }
+        }
+        Err(e) => report.push_str(&format!("Latest release: lookup failed ({e})\n")),
+    }
+
+    let warnings = extension.take_warnings(worktree);
+    if !warnings.is_empty() {
+        report.push_str("Warnings:\n");
+        for warning in warnings {
+            report.push_str(&format!("  - {warning}\n"));
+        }
+    }
+
+    if let Some(log) = failures::read() {
+        report.push_str(&format!(
+            "Recorded install failures ({}):\n{log}",
+            failures::FAILURE_LOG
+        ));
+    }
+
+    if let Some(tail) = server_log::tail() {
+        report.push_str(&format!(
+            "Server stderr (end of {}):\n{tail}\n",
+            server_log::STDERR_LOG
+        ));
+    }
+
+    report.push_str("Extension directory:\n");
+    let mut entries: Vec<PathBuf> = fs::read_dir(".")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    if entries.is_empty() {
+        report.push_str("  (empty)\n");
+    }
+    for entry in entries {
+        let binary = entry.join(platform.binary_file_name());
+        let state = if binary.exists() {
+            "binary present"
+        } else if entry.is_dir() {
+            "binary missing"
+        } else {
+            "file"
+        };
+        report.push_str(&format!("  - {} ({state})\n", entry.display()));
+    }
+
+    Ok(report.trim_end().to_string())
+}
+
+/// Walks through binary resolution and release selection like a language server start,
+/// without downloading, extracting or removing anything.
+fn dry_run(extension: &HarperExtension, worktree: &zed::Worktree) -> Result<String> {
+    let options = config::options(worktree);
+    if let Some(binary) = extension.find_binary(worktree, &options)? {
+        return Ok(format!(
+            "Would launch {} from {} (version {}); nothing would be downloaded.",
+            binary.path.display(),
+            binary.source.describe(),
+            binary.version.as_deref().unwrap_or("unknown")
+        ));
+    }
+
+    let platform = Platform::for_worktree(worktree);
+    let pinned = &options.version;
+    if let Some(pinned) = pinned
+        && let Some((dir, binary)) = installed_versions(platform)
+            .into_iter()
+            .find(|(dir, _)| is_version_dir(Path::new(dir), pinned))
+    {
+        return Ok(format!(
+            "Would launch the installed pinned version {dir} ({}); nothing would be downloaded.",
+            binary.display()
+        ));
+    }
+
+    let repo = config::release_repo(&options);
+    let release = extension
+        .installer
+        .preview_release(&repo, pinned.as_deref())?;
+    let target = platform.asset_target();
+    let asset =
+        target
+            .find_asset(NAME, &release.assets)
+            .ok_or_else(|| InstallError::AssetNotFound {
+                target: target.triple(),
+            })?;
+
+    let binary_path =
+        PathBuf::from(target.version_dir(NAME, &release.version)).join(platform.binary_file_name());
+    let install_path = std::env::current_dir()
+        .map(|dir| dir.join(&binary_path))
+        .unwrap_or_else(|_| binary_path.clone());
+
+    let mut report = format!(
+        "Release: {repo} {} ({})\nTarget: {}\nAsset: {}{}\nDownload: {}\nInstall path: {}\n",
+        release.version,
+        if pinned.is_some() { "pinned" } else { "latest" },
+        target.triple(),
+        asset.name,
+        if asset.name == target.asset_name() {
+            String::new()
+        } else {
+            format!(" (fuzzy match for {})", target.asset_name())
+        },
+        asset.download_url,
+        install_path.display()
+    );
+    match platform::archive_type(&asset.name) {
+        Some(file_type) => report.push_str(&format!("Archive: {file_type:?}\n")),
+        None => report.push_str("Archive: unsupported format; the install would fail\n"),
+    }
+    if binary_path.exists() {
+        report.push_str("This version is already installed; nothing would be downloaded\n");
+    }
+
+    Ok(report.trim_end().to_string())
+}
+
+/// Prepares a project-wide lint with a harper-cli matching the harper-ls in use.
+///
+/// The extension API cannot run programs, so the lint itself is left to Zed's terminal.
+fn lint(extension: &HarperExtension, worktree: &zed::Worktree) -> Result<String> {
+    let platform = Platform::for_worktree(worktree);
+    let options = config::options(worktree);
+    let version = extension
+        .find_binary(worktree, &options)?
+        .and_then(|binary| binary.version)
+        .or_else(|| options.version.clone());
+
+    let cli = cli::install(
+        extension.installer.as_ref(),
+        &config::release_repo(&options),
+        platform,
+        version.as_deref(),
+    )?;
+    let command = cli::lint_command(
+        platform,
+        &cli,
+        &worktree.root_path(),
+        &cli::lint_args(&server_section(worktree)),
+    );
+
+    Ok(format!(
+        "harper-cli is installed at {}.\n\nRun this in Zed's terminal to lint every tracked prose file and write the findings to {} in the worktree root. Of your settings, only `dialect` is passed to harper-cli:\n\n```\n{command}\n```",
+        cli.display(),
+        cli::REPORT_FILE
+    ))
+}
+
+/// Renders the output of the `/harper lint` command as a shareable report. Extensions
+/// cannot write into the worktree, so it is printed for the user to save.
+fn report(worktree: &zed::Worktree, args: &[String]) -> Result<String> {
+    let output = worktree.read_text_file(cli::REPORT_FILE).map_err(|_| {
+        format!(
+            "Could not read {} in the worktree root; run the command from `/harper lint` first",
+            cli::REPORT_FILE
+        )
+    })?;
+    let findings = report::parse(&output);
+    if findings.is_empty() {
+        return Ok(format!("{} contains no findings.", cli::REPORT_FILE));
+    }
+
+    match args.first().map(String::as_str) {
+        None | Some("markdown") => Ok(format!(
+            "Save this as `harper-report.md` to share it:\n\n{}",
+            report::markdown(&findings)
+        )),
+        Some("html") => Ok(format!(
+            "Save this as `harper-report.html` to share it:\n\n```html\n{}```",
+            report::html(&findings)
+        )),
+        Some(format) => Err(format!(
+            "Unknown report format {format:?}; expected markdown or html"
+        )),
+    }
+}
+
+/// Prints the merged configuration harper-ls receives, so CI can lint with the same
+/// dialect, linters and dictionaries as the editor.
+fn export(worktree: &zed::Worktree) -> Result<String> {
+    let mut warnings = Vec::new();
+    let section = config::portable_configuration(worktree, &mut warnings)?;
+    let json = serde_json::to_string_pretty(&section).map_err(|e| e.to_string())?;
+
+    let mut report = format!(
+        "The `{NAME}` settings of this worktree, with the extension options applied and paths relative to the worktree root. Machine-specific values, such as default dictionary locations and the dialect of your locale, are left out, so it works on any machine. Add it to the project's `.zed/settings.json` as `lsp.{NAME}.settings.{NAME}` to share it:\n\n```json\n{json}\n```"
+    );
+    for warning in warnings {
+        report.push_str(&format!("\n- {warning}"));
+    }
+    Ok(report)
+}
+
+/// Prints the settings schema; extensions cannot write into the worktree's `.zed` folder.
+fn schema() -> Result<String> {
+    let json =
+        serde_json::to_string_pretty(&schema::settings_schema()).map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        "Save this as `{}` and add it to the JSON language server's schemas for `.zed/settings.json` to get completion and validation of `lsp.{NAME}`:\n\n```json\n{json}\n```",
+        schema::SCHEMA_FILE
+    ))
+}
+
+/// Reports writing statistics for worktree files, defaulting to the README.
+///
+/// Extensions cannot list worktree files, so they are passed as arguments.
+fn stats(worktree: &zed::Worktree, files: &[String]) -> Result<String> {
+    let default_files = ["README.md".to_string()];
+    let files = if files.is_empty() {
+        &default_files[..]
+    } else {
+        files
+    };
+
+    let mut collected = Vec::new();
+    let mut unreadable = Vec::new();
+    for file in files {
+        match worktree.read_text_file(file) {
+            Ok(text) => collected.push((file.clone(), TextStats::from_text(&text))),
+            Err(_) => unreadable.push(file.as_str()),
+        }
+    }
+
+    if collected.is_empty() {
+        return Err(format!("Could not read {}", unreadable.join(", ")));
+    }
+
+    let mut report = stats::report(&collected);
+    if !unreadable.is_empty() {
+        report.push_str(&format!(
+            "\nSkipped unreadable files: {}\n",
+            unreadable.join(", ")
+        ));
+    }
+    report.push_str(&format!(
+        "\nFor the rules Harper flags in these files, see `{}` written by the command from `/harper lint`.",
+        cli::REPORT_FILE
+    ));
+
+    Ok(report)
+}
+
+fn hook(worktree: &zed::Worktree) -> String {
+    let (pre_commit, husky) = cli::hooks(&cli::lint_args(&server_section(worktree)));
+
+    format!(
+        "Both hooks expect `harper-cli` on PATH (see `/harper lint` for a managed copy) and pass the `dialect` the extension sends to harper-ls as `--dialect`. No other settings are carried over, so linters you disabled and words in your dictionaries are still reported.\n\nAdd to `.pre-commit-config.yaml`:\n\n```yaml\n{pre_commit}```\n\nOr add to `.husky/pre-commit`:\n\n```sh\n{husky}```"
+    )
+}
+
+/// Lists words from the glossary, dependency manifests and code identifiers that the
+/// workspace dictionary does not contain yet.
+///
+/// The extension can neither list nor write worktree files, so source files are named by
+/// the user and the result is printed for them to append.
+fn dictionary(worktree: &zed::Worktree, files: &[String]) -> Result<String> {
+    let dictionary_path = server_section(worktree)
+        .get("workspaceDictPath")
+        .and_then(Value::as_str)
+        .unwrap_or(dictionary::WORKSPACE_DICTIONARY)
+        .to_string();
+    // The effective configuration holds the path made absolute for harper-ls, but the
+    // worktree only reads paths relative to its root.
+    let root = worktree.root_path();
+    let relative_path = Path::new(&dictionary_path)
+        .strip_prefix(&root)
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| dictionary_path.clone());
+    let outside_worktree = Path::new(&relative_path).is_absolute();
+    let known = worktree
+        .read_text_file(&relative_path)
+        .map(|text| dictionary::entries(&text))
+        .unwrap_or_default();
+
+    let mut words = BTreeSet::new();
+    let mut sources = Vec::new();
+    for manifest in dictionary::MANIFESTS {
+        if let Ok(text) = worktree.read_text_file(manifest) {
+            words.extend(dictionary::manifest_words(manifest, &text));
+            sources.push(manifest.to_string());
+        }
+    }
+
+    let mut unreadable = Vec::new();
+    let glossary = config::options(worktree).glossary;
+    if let Some(glossary) = glossary {
+        match worktree.read_text_file(&glossary) {
+            Ok(text) => {
+                words.extend(dictionary::glossary_words(&text));
+                sources.push(glossary);
+            }
+            Err(_) => unreadable.push(glossary),
+        }
+    }
+
+    for file in files {
+        match worktree.read_text_file(file) {
+            Ok(text) => {
+                words.extend(dictionary::identifier_words(&text));
+                sources.push(file.clone());
+            }
+            Err(_) => unreadable.push(file.clone()),
+        }
+    }
+    words.retain(|word| !known.contains(word));
+
+    if sources.is_empty() {
+        return Err(format!(
+            "No {} or glossary found; pass source files to collect words from, e.g. `/harper dictionary src/lib.rs`",
+            dictionary::MANIFESTS.join(", ")
+        ));
+    }
+
+    let mut report = format!("Words from {}.\n\n", sources.join(", "));
+    report.push_str(&if words.is_empty() {
+        format!("No new words; {dictionary_path} already covers them.\n")
+    } else {
+        format!(
+            "Append these words to {dictionary_path} so Harper accepts them in comments and docs:\n\n```\n{}\n```\n",
+            words.into_iter().collect::<Vec<_>>().join("\n")
+        )
+    });
+    if !unreadable.is_empty() {
+        report.push_str(&format!(
+            "\nSkipped unreadable files: {}\n",
+            unreadable.join(", ")
+        ));
+    }
+    if outside_worktree {
+        report.push_str(&format!(
+            "\n{dictionary_path} is outside the worktree and cannot be read, so words it already contains may be listed.\n"
+        ));
+    }
+
+    Ok(report)
+}
+
+/// Returns the `harper-ls` section of the effective workspace configuration.
+fn server_section(worktree: &zed::Worktree) -> Value {
+    config::effective_configuration(worktree, None, &mut Vec::new())
+        .ok()
+        .and_then(|configuration| configuration.get(NAME).cloned())
+        .unwrap_or_else(|| Value::Object(Default::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_everything_recognizes_catch_all_globs() {
+        for pattern in ["**", "**/*", "./**", "/**/"] {
+            assert!(matches_everything(pattern), "{pattern}");
+        }
+        for pattern in ["*", "vendor/**", "**/*.md", "docs/**/*"] {
+            assert!(!matches_everything(pattern), "{pattern}");
+        }
+    }
+}
@@ -0,0 +1,611 @@
+use crate::error::ConfigError;
+use crate::platform::Platform;
+use crate::sandbox::Sandbox;
+use crate::settings::{ExtensionOptions, HarperSettings, ServerSettings};
+use crate::{
+    DEFAULT_RELEASE_REPO, NAME, config_dir, data_dir, env_var, linters, manifest, presets, wsl,
+};
+use std::path::{Path, PathBuf};
+use zed_extension_api::{
+    self as zed,
+    serde_json::{self, Value},
+};
+
+/// Vendored and build-output directories, keyed by the manifest that implies them.
+const VENDORED_DIRS: &[(&str, &[&str])] = &[
+    ("package.json", &["**/node_modules/**", "**/dist/**"]),
+    ("Cargo.toml", &["**/target/**"]),
+    ("go.mod", &["**/vendor/**"]),
+    ("composer.json", &["**/vendor/**"]),
+];
+
+/// Dialects `harper-ls` accepts for `dialect`.
+pub const DIALECTS: &[&str] = &["American", "British", "Canadian", "Australian", "Indian"];
+
+/// File name patterns of common code generators' output.
+const GENERATED_FILES: &[&str] = &[
+    "**/*.pb.go",
+    "**/*_pb2.py",
+    "**/*.pb.ts",
+    "**/*.generated.*",
+    "**/*.g.dart",
+    "**/*.freezed.dart",
+];
+
+/// Returns the extension options from the top level of `lsp.harper-ls.settings`, or the
+/// defaults if the settings are invalid; the error surfaces with the workspace configuration.
+pub fn options(worktree: &zed::Worktree) -> ExtensionOptions {
+    HarperSettings::for_worktree(worktree, &mut Vec::new())
+        .map(|settings| settings.options)
+        .unwrap_or_default()
+}
+
+/// Returns the `owner/repo` Harper releases are downloaded from.
+pub fn release_repo(options: &ExtensionOptions) -> String {
+    options
+        .repository
+        .clone()
+        .filter(|repo| is_repo(repo))
+        .unwrap_or_else(|| DEFAULT_RELEASE_REPO.to_string())
+}
+
+/// Returns whether `repo` has the `owner/name` form of a GitHub repository.
+pub fn is_repo(repo: &str) -> bool {
+    repo.split_once('/')
+        .is_some_and(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+}
+
+/// Returns the workspace configuration sent to the server, in the format of
+/// `server_version` (the latest release when `None`).
+pub fn effective_configuration(
+    worktree: &zed::Worktree,
+    server_version: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<Value, ConfigError> {
+    let settings = HarperSettings::for_worktree(worktree, warnings)?;
+    let HarperSettings {
+        mut server,
+        options,
+        mut other,
+    } = settings;
+
+    let rules = server_version.map(manifest::rules).unwrap_or_default();
+    workspace_configuration(&mut server, &options, &rules, worktree, warnings);
+
+    other.insert(NAME.to_string(), server.into_value(server_version)?);
+    Ok(Value::Object(other))
+}
+
+/// Returns the `harper-ls` section as the worktree's settings define it, for `/harper
+/// export`: paths are relative to the worktree root, and values derived from the machine,
+/// such as default dictionary locations and the locale's dialect, are left out, so the
+/// section works wherever the project is checked out.
+pub fn portable_configuration(
+    worktree: &zed::Worktree,
+    warnings: &mut Vec<String>,
+) -> Result<Value, ConfigError> {
+    let HarperSettings {
+        mut server,
+        options,
+        ..
+    } = HarperSettings::for_worktree(worktree, warnings)?;
+
+    apply_options(&mut server, &options, &[], worktree, warnings);
+    if server.dialect.is_none() {
+        server.dialect = repo_dialect(worktree).map(str::to_string);
+    }
+    apply_relative_paths(&mut server, &options, warnings);
+
+    server.into_value(None)
+}
+
+/// The configuration sent while the server runs in safe mode after repeated crashes:
+/// an empty `harper-ls` section, so the server starts with its built-in defaults.
+pub fn safe_mode_configuration() -> Value {
+    serde_json::json!({ NAME: {} })
+}
+
+/// The configuration sent while `/harper pause` is in effect: every file is excluded,
+/// which silences all linters, including ones the extension does not know.
+pub fn paused_configuration() -> Value {
+    serde_json::json!({ NAME: { "excludePatterns": ["**"] } })
+}
+
+/// The user's settings that could make harper-ls crash, most likely first: settings the
+/// extension does not know (often experimental ones), dictionaries and ignore files it
+/// has to read, then the linter and parser options it has to apply.
+pub fn crash_suspects(worktree: &zed::Worktree) -> Vec<String> {
+    let Ok(HarperSettings {
+        mut server,
+        options,
+        other,
+    }) = HarperSettings::for_worktree(worktree, &mut Vec::new())
+    else {
+        return Vec::new();
+    };
+
+    let mut suspects: Vec<String> = server
+        .other
+        .keys()
+        .chain(other.keys())
+        .map(|key| format!("`{key}`"))
+        .collect();
+    for (key, path) in server.paths_mut() {
+        if let Some(path) = path {
+            suspects.push(format!("`{key}` ({path})"));
+        }
+    }
+    if options.glossary.is_some() {
+        suspects.push("`glossary`".to_string());
+    }
+    if !server.linters.is_empty() {
+        suspects.push("`linters`".to_string());
+    }
+    for (key, set) in [
+        ("dialect", server.dialect.is_some()),
+        ("isolateEnglish", server.isolate_english.is_some()),
+        ("markdown", server.markdown.is_some()),
+        ("codeActions", server.code_actions.is_some()),
+    ] {
+        if set {
+            suspects.push(format!("`{key}`"));
+        }
+    }
+    suspects
+}
+
+/// Translates the extension options into the `harper-ls` section and fills in derived
+/// values. `rules` are the linters of the server's version, if recorded. Problems worth
+/// surfacing to the user are pushed onto `warnings`.
+fn workspace_configuration(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    rules: &[String],
+    worktree: &zed::Worktree,
+    warnings: &mut Vec<String>,
+) {
+    apply_options(server, options, rules, worktree, warnings);
+    apply_dialect(server, worktree);
+    apply_working_directory(server, options, worktree);
+    apply_wsl_paths(server, worktree);
+    apply_sandbox_paths(server, worktree, warnings);
+    apply_default_dictionaries(server, worktree);
+}
+
+/// Applies the extension options and what the worktree's files imply, which hold on any
+/// machine the project is checked out on.
+fn apply_options(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    rules: &[String],
+    worktree: &zed::Worktree,
+    warnings: &mut Vec<String>,
+) {
+    linters::normalize(&mut server.linters, rules, warnings);
+    apply_suppress(server, options, rules, warnings);
+    apply_severities(server, options, rules, warnings);
+    apply_mode(server, options, rules, warnings);
+    apply_preset(server, options, worktree, warnings);
+    server.exclude(&options.exclude);
+    apply_overrides(server, options, warnings);
+    apply_vendored_exclude(server, options, worktree);
+    apply_generated_exclude(server, options, worktree);
+    apply_enforced_dialect(server, options, warnings);
+}
+
+/// Disables the linters listed in `suppress`, overriding their `linters` entries.
+fn apply_suppress(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    rules: &[String],
+    warnings: &mut Vec<String>,
+) {
+    for name in &options.suppress {
+        server
+            .linters
+            .insert(linters::resolve(name, rules, warnings), false);
+    }
+}
+
+/// Applies `severities` as far as harper-ls allows: it has one `diagnosticSeverity` for
+/// all linters, so only `"off"` entries take effect, by disabling the linter.
+fn apply_severities(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    rules: &[String],
+    warnings: &mut Vec<String>,
+) {
+    for (linter, severity) in &options.severities {
+        let linter = linters::resolve(linter, rules, warnings);
+        if severity == "off" {
+            server.linters.entry(linter).or_insert(false);
+        } else {
+            warnings.push(format!(
+                "Ignoring severity {severity:?} for {linter}: harper-ls applies `diagnosticSeverity` to every linter, so only \"off\" is supported per linter"
+            ));
+        }
+    }
+}
+
+/// Expands `mode` into linter toggles: `grammar` switches `SpellCheck` off and leaves the
+/// server's defaults for the rest, `spelling` switches the other linters of the server's
+/// version off, or the known ones when its rules were not recorded. Linters configured by
+/// the user keep their value.
+fn apply_mode(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    rules: &[String],
+    warnings: &mut Vec<String>,
+) {
+    match options.mode.as_deref() {
+        None | Some("full") => {}
+        Some("grammar") => {
+            server
+                .linters
+                .entry("SpellCheck".to_string())
+                .or_insert(false);
+        }
+        Some("spelling") => {
+            let known = linters::KNOWN.iter().map(|linter| linter.to_string());
+            let all: Vec<String> = if rules.is_empty() {
+                known.collect()
+            } else {
+                rules.to_vec()
+            };
+            for linter in all.into_iter().filter(|linter| linter != "SpellCheck") {
+                server.linters.entry(linter).or_insert(false);
+            }
+        }
+        Some(mode) => warnings.push(format!(
+            "Unknown mode {mode:?}; expected full, spelling or grammar"
+        )),
+    }
+}
+
+fn apply_preset(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    worktree: &zed::Worktree,
+    warnings: &mut Vec<String>,
+) {
+    let name = match &options.preset {
+        Some(name) => name,
+        None => match presets::project_kind(worktree)
+            .and_then(|kind| options.project_presets.get(kind))
+        {
+            Some(name) => name,
+            None => return,
+        },
+    };
+
+    match presets::find(name) {
+        Some(preset) => preset.apply(server),
+        None => warnings.push(format!(
+            "Unknown preset {name:?}; expected one of {}",
+            presets::PRESETS
+                .iter()
+                .map(|preset| preset.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Compiles `overrides` entries that disable Harper for a path into `excludePatterns`.
+/// harper-ls has a single configuration per workspace, so per-path rule changes are
+/// reported instead of applied.
+fn apply_overrides(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    warnings: &mut Vec<String>,
+) {
+    for entry in &options.overrides {
+        let Some(path) = &entry.path else {
+            warnings.push("Ignoring override without a `path`".to_string());
+            continue;
+        };
+
+        if entry.enabled == Some(false) {
+            server.exclude([path]);
+        } else {
+            warnings.push(format!(
+                "Ignoring override for {path}: harper-ls applies one configuration per workspace, so only `enabled: false` overrides are supported"
+            ));
+        }
+    }
+}
+
+fn apply_vendored_exclude(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    worktree: &zed::Worktree,
+) {
+    if !options.exclude_vendored {
+        return;
+    }
+
+    let patterns = VENDORED_DIRS
+        .iter()
+        .filter(|(manifest, _)| worktree.read_text_file(manifest).is_ok())
+        .flat_map(|(_, patterns)| patterns.iter().copied());
+
+    server.exclude(patterns);
+}
+
+/// Excludes files named like generator output and those `.gitattributes` marks as
+/// `linguist-generated`. The extension cannot read every file, so `@generated` or
+/// "DO NOT EDIT" headers are not detected.
+fn apply_generated_exclude(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    worktree: &zed::Worktree,
+) {
+    if !options.exclude_generated {
+        return;
+    }
+
+    let attributes = worktree
+        .read_text_file(".gitattributes")
+        .unwrap_or_default();
+    let marked: Vec<String> = attributes
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().filter(|pattern| !pattern.starts_with('#'))?;
+            fields
+                .any(|attribute| {
+                    matches!(attribute, "linguist-generated" | "linguist-generated=true")
+                })
+                .then(|| gitattributes_glob(pattern))
+        })
+        .collect();
+
+    server.exclude(GENERATED_FILES);
+    server.exclude(marked);
+}
+
+/// Converts a `.gitattributes` pattern, which matches at any depth unless it contains a
+/// slash, into a glob relative to the worktree root.
+fn gitattributes_glob(pattern: &str) -> String {
+    match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{pattern}"),
+    }
+}
+
+/// Resolves relative paths in the server section against `working_directory` (the
+/// worktree root by default). harper-ls resolves them against its own working
+/// directory, which extensions cannot set and which depends on how Zed spawned it.
+fn apply_working_directory(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    worktree: &zed::Worktree,
+) {
+    let root = worktree.root_path();
+    let base = match &options.working_directory {
+        Some(dir) if is_absolute(dir) => PathBuf::from(dir),
+        Some(dir) => Path::new(&root).join(dir),
+        None => PathBuf::from(root),
+    };
+
+    for (_, path) in server.paths_mut() {
+        if let Some(path) = path
+            && !is_absolute(path)
+        {
+            *path = base.join(&*path).to_string_lossy().into_owned();
+        }
+    }
+}
+
+/// Makes relative paths in the server section relative to the worktree root instead of
+/// `working_directory`, and leaves out absolute ones, which only hold on this machine.
+fn apply_relative_paths(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    warnings: &mut Vec<String>,
+) {
+    let base = options
+        .working_directory
+        .as_deref()
+        .filter(|dir| !is_absolute(dir));
+    for (key, path) in server.paths_mut() {
+        match path {
+            Some(value) if is_absolute(value) => {
+                warnings.push(format!(
+                    "{key} {value} is absolute, so it is left out of the export"
+                ));
+                *path = None;
+            }
+            Some(value) => {
+                if let Some(base) = base {
+                    *value = Path::new(base).join(&*value).to_string_lossy().into_owned();
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Whether a path is absolute on any host: `/`-rooted, `~`-relative, a UNC path or one
+/// with a Windows drive letter. `Path::is_absolute` only knows the extension's own
+/// platform.
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\', '~'])
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Rewrites Windows paths for a server running inside WSL.
+fn apply_wsl_paths(server: &mut ServerSettings, worktree: &zed::Worktree) {
+    let Some(distro) = wsl::distro(worktree) else {
+        return;
+    };
+
+    for (_, path) in server.paths_mut() {
+        if let Some(path) = path {
+            *path = wsl::to_linux_path(path, &distro);
+        }
+    }
+}
+
+/// Points the dictionaries at sandbox-writable locations under Flatpak or Snap, and
+/// warns about configured paths the sandbox cannot reach.
+fn apply_sandbox_paths(
+    server: &mut ServerSettings,
+    worktree: &zed::Worktree,
+    warnings: &mut Vec<String>,
+) {
+    let env = worktree.shell_env();
+    let Some(sandbox) = Sandbox::detect(&env) else {
+        return;
+    };
+
+    for (key, path) in server.paths_mut() {
+        if let Some(path) = path
+            && !sandbox.can_access(&env, worktree, path)
+        {
+            warnings.push(format!(
+                "{key} {path} is probably not accessible from the {sandbox:?} sandbox Zed runs in"
+            ));
+        }
+    }
+
+    if let Some(dirs) = sandbox.dirs(&env) {
+        server
+            .user_dict_path
+            .get_or_insert_with(|| format!("{}/harper-ls/dictionary.txt", dirs.config));
+        server
+            .file_dict_path
+            .get_or_insert_with(|| format!("{}/harper-ls/file_dictionaries/", dirs.data));
+    }
+}
+
+/// Makes the dictionary locations explicit, using the same per-OS directories harper-ls
+/// falls back to. Inside WSL the server's own defaults already point into the
+/// distribution.
+fn apply_default_dictionaries(server: &mut ServerSettings, worktree: &zed::Worktree) {
+    if wsl::distro(worktree).is_some() {
+        return;
+    }
+
+    let env = worktree.shell_env();
+    let platform = Platform::for_worktree(worktree);
+    if server.user_dict_path.is_none()
+        && let Some(dir) = config_dir(platform, &env)
+    {
+        server.user_dict_path = Some(format!("{dir}/harper-ls/dictionary.txt"));
+    }
+    if server.file_dict_path.is_none()
+        && let Some(dir) = data_dir(platform, &env)
+    {
+        server.file_dict_path = Some(format!("{dir}/harper-ls/file_dictionaries/"));
+    }
+}
+
+/// Applies `enforce_dialect`: Harper's spell check flags spellings of other dialects, so
+/// consistency only needs `SpellCheck` on and the winning variant as `dialect`. `"auto"`
+/// leaves the dialect to be derived as usual.
+fn apply_enforced_dialect(
+    server: &mut ServerSettings,
+    options: &ExtensionOptions,
+    warnings: &mut Vec<String>,
+) {
+    let Some(winner) = options.enforce_dialect.as_deref() else {
+        return;
+    };
+
+    if winner != "auto" {
+        if !DIALECTS.contains(&winner) {
+            warnings.push(format!(
+                "Unknown enforce_dialect {winner:?}; expected auto or one of {}",
+                DIALECTS.join(", ")
+            ));
+            return;
+        }
+        if let Some(dialect) = &server.dialect
+            && dialect != winner
+        {
+            warnings.push(format!(
+                "enforce_dialect {winner:?} overrides the `dialect` {dialect:?} in the harper-ls section"
+            ));
+        }
+        server.dialect = Some(winner.to_string());
+    }
+
+    if server.linters.insert("SpellCheck".to_string(), true) == Some(false) {
+        warnings.push(
+            "enforce_dialect turns SpellCheck back on, which `linters`, `suppress` or `mode` disabled"
+                .to_string(),
+        );
+    }
+}
+
+/// Fills in the dialect when the user has not configured one, preferring hints
+/// committed to the repository over the user's locale.
+fn apply_dialect(server: &mut ServerSettings, worktree: &zed::Worktree) {
+    if server.dialect.is_some() {
+        return;
+    }
+
+    server.dialect = repo_dialect(worktree)
+        .or_else(|| locale_dialect(&worktree.shell_env()))
+        .map(str::to_string);
+}
+
+/// Looks for a dialect in `.editorconfig` (`spelling_language`), cspell (`language`)
+/// and Vale (`Lang`) configuration at the worktree root.
+fn repo_dialect(worktree: &zed::Worktree) -> Option<&'static str> {
+    let ini_value = |file: &str, key: &str| {
+        let text = worktree.read_text_file(file).ok()?;
+        text.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        })
+    };
+
+    let editorconfig = || ini_value(".editorconfig", "spelling_language");
+    let vale = || ini_value(".vale.ini", "Lang");
+    let cspell = || {
+        ["cspell.json", ".cspell.json", "cspell.config.json"]
+            .iter()
+            .find_map(|file| {
+                let text = worktree.read_text_file(file).ok()?;
+                let config: Value = serde_json::from_str(&text).ok()?;
+                config.get("language")?.as_str().map(str::to_string)
+            })
+    };
+
+    let dialect = |languages: String| {
+        languages
+            .split(',')
+            .find_map(|language| dialect_for_locale(language.trim()))
+    };
+
+    editorconfig()
+        .and_then(dialect)
+        .or_else(|| cspell().and_then(dialect))
+        .or_else(|| vale().and_then(dialect))
+}
+
+fn locale_dialect(env: &[(String, String)]) -> Option<&'static str> {
+    let locale = ["LC_ALL", "LANG"]
+        .iter()
+        .find_map(|name| env_var(env, name))?;
+
+    dialect_for_locale(locale)
+}
+
+/// Maps a locale such as `en_GB.UTF-8` or `en-GB` to a Harper dialect.
+fn dialect_for_locale(locale: &str) -> Option<&'static str> {
+    let locale = locale.split(['.', '@']).next()?.replace('-', "_");
+
+    match locale.as_str() {
+        "en_US" => Some("American"),
+        "en_GB" => Some("British"),
+        "en_CA" => Some("Canadian"),
+        "en_AU" => Some("Australian"),
+        "en_IN" => Some("Indian"),
+        _ => None,
+    }
+}
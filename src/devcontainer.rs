@@ -0,0 +1,34 @@
+use crate::{BinarySource, HarperBinary, server_args};
+use std::path::PathBuf;
+use zed_extension_api as zed;
+
+const CLI: &str = "devcontainer";
+const CONFIG_FILES: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+/// Returns a binary that runs harper-ls from the container's `PATH` through the
+/// devcontainer CLI, when the worktree has a dev container configuration.
+pub fn binary(worktree: &zed::Worktree) -> Option<HarperBinary> {
+    CONFIG_FILES
+        .iter()
+        .find(|file| worktree.read_text_file(file).is_ok())?;
+    let cli = worktree.which(CLI)?;
+
+    let args = [
+        "exec",
+        "--workspace-folder",
+        &worktree.root_path(),
+        crate::NAME,
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .chain(server_args(None))
+    .collect();
+
+    Some(HarperBinary {
+        path: PathBuf::from(cli),
+        args,
+        env: Some(worktree.shell_env()),
+        source: BinarySource::DevContainer,
+        version: None,
+    })
+}
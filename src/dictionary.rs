@@ -0,0 +1,414 @@
+use std::collections::BTreeSet;
+use zed_extension_api::serde_json::{self, Value};
+
+/// Default location of the workspace dictionary harper-ls reads, relative to the worktree root.
+pub const WORKSPACE_DICTIONARY: &str = ".harper-dictionary.txt";
+
+/// Shortest identifier part worth adding; shorter ones are mostly abbreviations like `id`.
+const MIN_WORD_LEN: usize = 3;
+
+/// Collects the words making up compound identifiers (`parseConfig`, `max_retries`,
+/// `HTTPServer`) in source code.
+///
+/// Single words are skipped, as in comments they are as likely to be prose as code.
+pub fn identifier_words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|identifier| is_compound(identifier))
+        .flat_map(split_identifier)
+        .filter(|word| word.len() >= MIN_WORD_LEN && word.chars().all(char::is_alphabetic))
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn is_compound(identifier: &str) -> bool {
+    let trimmed = identifier.trim_matches('_');
+    let camel_case =
+        trimmed.chars().skip(1).any(char::is_uppercase) && trimmed.chars().any(char::is_lowercase);
+
+    trimmed.contains('_') || camel_case
+}
+
+/// Splits `snake_case`, `camelCase` and `PascalCase` identifiers into their words,
+/// keeping acronyms together (`HTTPServer` becomes `HTTP` and `Server`).
+fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    for part in identifier.split('_').filter(|part| !part.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let boundary = i > 0
+                && c.is_uppercase()
+                && (chars[i - 1].is_lowercase()
+                    || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c);
+        }
+        words.push(word);
+    }
+
+    words
+}
+
+/// Returns the entries of a dictionary file, lowercased for comparison.
+pub fn entries(text: &str) -> BTreeSet<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Manifests whose dependency names are offered for the dictionary.
+pub const MANIFESTS: &[&str] = &["Cargo.toml", "package.json", "pyproject.toml", "go.mod"];
+
+/// Collects the words of the package and dependency names declared in a manifest.
+pub fn manifest_words(manifest: &str, text: &str) -> BTreeSet<String> {
+    let names = match manifest {
+        "package.json" => package_json_names(text),
+        "go.mod" => go_mod_names(text),
+        _ => toml_names(text),
+    };
+
+    names
+        .iter()
+        .flat_map(|name| name.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.len() >= MIN_WORD_LEN && word.chars().all(char::is_alphabetic))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn package_json_names(text: &str) -> Vec<String> {
+    let Ok(manifest) = serde_json::from_str::<Value>(text) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = manifest
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .into_iter()
+        .collect();
+    for section in [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ] {
+        if let Some(dependencies) = manifest.get(section).and_then(Value::as_object) {
+            names.extend(dependencies.keys().cloned());
+        }
+    }
+
+    names
+}
+
+/// Reads module paths from `module` and `require` lines, keeping their last segment
+/// (`github.com/spf13/cobra` becomes `cobra`).
+fn go_mod_names(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut in_require = false;
+
+    for line in text.lines().map(str::trim) {
+        let path = if line == "require (" {
+            in_require = true;
+            continue;
+        } else if in_require && line == ")" {
+            in_require = false;
+            continue;
+        } else if in_require {
+            line.split_whitespace().next()
+        } else if let Some(rest) = line
+            .strip_prefix("module ")
+            .or_else(|| line.strip_prefix("require "))
+        {
+            rest.split_whitespace().next()
+        } else {
+            None
+        };
+
+        if let Some(segment) = path.and_then(|path| path.rsplit('/').next()) {
+            names.push(segment.to_string());
+        }
+    }
+
+    names
+}
+
+/// Reads package names from `Cargo.toml` and `pyproject.toml`: the `name` of the package,
+/// keys of dependency tables and the entries of PEP 621 dependency arrays.
+fn toml_names(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut table = String::new();
+    let mut in_array = false;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = header.trim_matches(['[', ']']).to_string();
+            in_array = false;
+            continue;
+        }
+
+        if in_array || line.starts_with("dependencies = [") {
+            in_array = !line.ends_with(']');
+            names.extend(
+                line.split('"')
+                    .skip(1)
+                    .step_by(2)
+                    .filter_map(|requirement| {
+                        requirement
+                            .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                            .next()
+                    })
+                    .map(str::to_string),
+            );
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().trim_matches('"');
+        if table.ends_with("dependencies") {
+            names.push(key.split('.').next().unwrap_or(key).to_string());
+        } else if key == "name" && matches!(table.as_str(), "package" | "project" | "tool.poetry") {
+            names.push(value.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+
+    names
+}
+
+/// Collects the words of the terms a Markdown glossary defines: headings, bold terms
+/// starting a line (`**Term**: ...`), definition list terms followed by a `: ` line, and
+/// the first column of tables. HTML comments are skipped.
+pub fn glossary_words(text: &str) -> BTreeSet<String> {
+    let text = strip_comments(text);
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let mut terms = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(heading) = line.strip_prefix('#') {
+            terms.push(heading.trim_start_matches('#'));
+        } else if let Some(rest) = line.strip_prefix("**")
+            && let Some((term, _)) = rest.split_once("**")
+        {
+            terms.push(term);
+        } else if let Some(row) = line.strip_prefix('|') {
+            let cell = row.split('|').next().unwrap_or_default();
+            if !cell
+                .trim()
+                .chars()
+                .all(|c| c == '-' || c == ':' || c == ' ')
+            {
+                terms.push(cell);
+            }
+        } else if !line.is_empty() && lines.get(i + 1).is_some_and(|next| next.starts_with(": ")) {
+            terms.push(line);
+        }
+    }
+
+    terms
+        .iter()
+        .flat_map(|term| term.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.len() >= MIN_WORD_LEN && word.chars().all(char::is_alphabetic))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Removes `<!-- ... -->` comments, keeping line breaks so definition lists stay aligned.
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("<!--") {
+        stripped.push_str(before);
+        let (comment, after) = after.split_once("-->").unwrap_or((after, ""));
+        stripped.extend(comment.chars().filter(|&c| c == '\n'));
+        rest = after;
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn split_identifier_splits_camel_case() {
+        assert_eq!(split_identifier("parseConfig"), words(&["parse", "Config"]));
+        assert_eq!(
+            split_identifier("ReadTextFile"),
+            words(&["Read", "Text", "File"])
+        );
+    }
+
+    #[test]
+    fn split_identifier_splits_snake_case() {
+        assert_eq!(split_identifier("max_retries"), words(&["max", "retries"]));
+        assert_eq!(
+            split_identifier("__MAX_RETRY_COUNT"),
+            words(&["MAX", "RETRY", "COUNT"])
+        );
+    }
+
+    #[test]
+    fn split_identifier_keeps_acronyms_together() {
+        assert_eq!(split_identifier("HTTPServer"), words(&["HTTP", "Server"]));
+        assert_eq!(
+            split_identifier("parseJSONBody"),
+            words(&["parse", "JSON", "Body"])
+        );
+        assert_eq!(split_identifier("useURL"), words(&["use", "URL"]));
+    }
+
+    #[test]
+    fn identifier_words_skips_single_words() {
+        let text = "let tokenizer = newTokenizer(config_path);";
+        assert_eq!(
+            identifier_words(text),
+            BTreeSet::from([
+                "new".into(),
+                "tokenizer".into(),
+                "config".into(),
+                "path".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn toml_names_reads_every_dependency_table() {
+        let cargo = r#"
+[package]
+name = "zed-harper"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+tree-sitter.workspace = true
+
+[dev-dependencies]
+pretty_assertions = "1"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[features]
+default = ["fancy"]
+"#;
+        assert_eq!(
+            toml_names(cargo),
+            words(&[
+                "zed-harper",
+                "serde",
+                "tree-sitter",
+                "pretty_assertions",
+                "libc"
+            ])
+        );
+    }
+
+    #[test]
+    fn toml_names_reads_pep_621_arrays() {
+        let pyproject = r#"
+[project]
+name = "harper-docs"
+dependencies = [
+    "requests>=2",
+    "rich[jupyter]",
+]
+"#;
+        assert_eq!(
+            toml_names(pyproject),
+            words(&["harper-docs", "requests", "rich"])
+        );
+    }
+
+    #[test]
+    fn go_mod_names_reads_require_blocks() {
+        let go_mod = "\
+module github.com/acme/linter
+
+go 1.22
+
+require github.com/spf13/cobra v1.8.0
+
+require (
+\tgolang.org/x/text v0.14.0
+\tgithub.com/stretchr/testify v1.9.0 // indirect
+)
+";
+        assert_eq!(
+            go_mod_names(go_mod),
+            words(&["linter", "cobra", "text", "testify"])
+        );
+    }
+
+    #[test]
+    fn manifest_words_splits_package_names() {
+        let package_json =
+            r#"{"name": "@acme/doc-site", "devDependencies": {"eslint-plugin-markdown": "3"}}"#;
+        assert_eq!(
+            manifest_words("package.json", package_json),
+            BTreeSet::from([
+                "acme".into(),
+                "doc".into(),
+                "site".into(),
+                "eslint".into(),
+                "plugin".into(),
+                "markdown".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn glossary_words_reads_defined_terms() {
+        let glossary = "\
+# Glossary
+
+## Tokenizer
+
+**Lexeme**: the smallest unit of meaning.
+
+Morpheme
+: a part of a word.
+
+| Term | Meaning |
+|------|---------|
+| Corpus | a body of text |
+";
+        assert_eq!(
+            glossary_words(glossary),
+            BTreeSet::from([
+                "glossary".into(),
+                "tokenizer".into(),
+                "lexeme".into(),
+                "morpheme".into(),
+                "term".into(),
+                "corpus".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn glossary_words_skips_comments() {
+        let glossary = "\
+<!-- ## Draftterm -->
+<!--
+**Hiddenterm**: not ready yet.
+-->
+Visibleterm
+: shown in the docs.
+";
+        assert_eq!(
+            glossary_words(glossary),
+            BTreeSet::from(["visibleterm".into()])
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+
+const BINARY_PATH_HINT: &str =
+    "set `lsp.harper-ls.binary.path` in your Zed settings to a local harper-ls binary";
+
+#[derive(Debug)]
+pub enum InstallError {
+    ReleaseLookup(String),
+    UnsupportedArchitecture(&'static str),
+    AssetNotFound {
+        target: String,
+    },
+    UnsupportedArchive {
+        asset: String,
+    },
+    Download {
+        asset: String,
+        url: String,
+        version: String,
+        error: String,
+    },
+    MakeExecutable(String),
+    Staging {
+        path: String,
+        error: String,
+    },
+    NixOs,
+    PinnedReleaseNotFound {
+        pinned: String,
+        error: String,
+    },
+    BinaryMissing {
+        asset: String,
+        expected: String,
+    },
+    WrongTarget {
+        asset: String,
+        expected: String,
+        found: String,
+    },
+    UnsafeArchive {
+        asset: String,
+        entry: String,
+    },
+    InsufficientSpace {
+        asset: String,
+    },
+    DigestMismatch {
+        binary: String,
+        expected: String,
+        found: String,
+    },
+    Unverifiable {
+        binary: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    InvalidPath {
+        path: String,
+    },
+    BinaryNotFound {
+        path: String,
+    },
+    BinaryIsDirectory {
+        path: String,
+    },
+    NoBinary {
+        order: String,
+    },
+    ShimTargetMissing {
+        shim: String,
+        version: String,
+        target: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    InvalidSettings(String),
+    Disabled,
+}
+
+impl InstallError {
+    /// A coarse category for failure reports, free of paths and URLs.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::ReleaseLookup(_) | Self::PinnedReleaseNotFound { .. } => "release-lookup-failed",
+            Self::UnsupportedArchitecture(_) => "unsupported-architecture",
+            Self::AssetNotFound { .. } => "asset-missing",
+            Self::UnsupportedArchive { .. } => "unsupported-archive",
+            Self::Download { .. } => "download-failed",
+            Self::MakeExecutable(_) => "make-executable-failed",
+            Self::Staging { .. } => "staging-failed",
+            Self::NixOs => "nixos",
+            Self::BinaryMissing { .. } => "extraction-failed",
+            Self::WrongTarget { .. } => "wrong-target",
+            Self::UnsafeArchive { .. } => "unsafe-archive",
+            Self::InsufficientSpace { .. } => "insufficient-space",
+            Self::DigestMismatch { .. } => "digest-mismatch",
+            Self::Unverifiable { .. } => "unverifiable",
+        }
+    }
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReleaseLookup(e) => write!(
+                f,
+                "Failed to fetch latest Harper release: {e}. Check your network connection or {BINARY_PATH_HINT}"
+            ),
+            Self::UnsupportedArchitecture(arch) => write!(
+                f,
+                "{arch} architecture is not supported by Harper releases. Build harper-ls with `cargo install harper-ls` and {BINARY_PATH_HINT}"
+            ),
+            Self::AssetNotFound { target } => write!(
+                f,
+                "No compatible Harper binary found for {target}. Install harper-ls manually and {BINARY_PATH_HINT}"
+            ),
+            Self::UnsupportedArchive { asset } => write!(
+                f,
+                "Harper asset {asset} uses an archive format Zed cannot extract. Install harper-ls manually and {BINARY_PATH_HINT}"
+            ),
+            Self::Download {
+                asset,
+                url,
+                version,
+                error,
+            } => write!(
+                f,
+                "Failed to download Harper {version} asset {asset} from {url}: {error}. Retry later or {BINARY_PATH_HINT}"
+            ),
+            Self::MakeExecutable(e) => write!(
+                f,
+                "Failed to make Harper binary executable: {e}. Check permissions of the extension directory or {BINARY_PATH_HINT}"
+            ),
+            Self::Staging { path, error } => write!(
+                f,
+                "Failed to stage the Harper install at {path}: {error}. Check permissions and free space of the extension directory or {BINARY_PATH_HINT}"
+            ),
+            Self::NixOs => write!(
+                f,
+                "Prebuilt Harper binaries cannot run on NixOS. Install `pkgs.harper` so harper-ls is on PATH, make `nix` available, or {BINARY_PATH_HINT}"
+            ),
+            Self::PinnedReleaseNotFound { pinned, error } => write!(
+                f,
+                "Failed to fetch pinned Harper release {pinned}: {error}. Check that `lsp.harper-ls.settings.version` names an existing release, remove the pin, or {BINARY_PATH_HINT}"
+            ),
+            Self::BinaryMissing { asset, expected } => write!(
+                f,
+                "Harper asset {asset} did not contain a harper-ls binary (expected {expected}), even after re-downloading. The release layout may have changed; report it upstream or {BINARY_PATH_HINT}"
+            ),
+            Self::WrongTarget {
+                asset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Harper asset {asset} contains {found}, not a {expected} binary, so it was not installed. The asset may be mislabeled upstream; report it or {BINARY_PATH_HINT}"
+            ),
+            Self::InsufficientSpace { asset } => write!(
+                f,
+                "Not enough disk space to install Harper asset {asset} in Zed's extension directory; the partial download was removed. Free up space and restart the language server, or {BINARY_PATH_HINT}"
+            ),
+            Self::DigestMismatch {
+                binary,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Harper binary from {binary} does not match `expected_sha256` {expected} (found {found}), so it is not used. Check that the digest is the one of the harper-ls binary of the pinned version, or {BINARY_PATH_HINT}"
+            ),
+            Self::Unverifiable { binary } => write!(
+                f,
+                "Harper binary {binary} cannot be read to check it against `expected_sha256`, so it is not used. Shares outside Zed's extension directory cannot be verified; remove `expected_sha256`, or {BINARY_PATH_HINT}"
+            ),
+            Self::UnsafeArchive { asset, entry } => write!(
+                f,
+                "Harper asset {asset} was rejected because its entry {entry} points outside the install directory, which a legitimate release never does. It was removed; report the release upstream and {BINARY_PATH_HINT}"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath { path } => write!(
+                f,
+                "Harper binary path {path} contains characters that are not valid UTF-8, which Zed cannot launch. Move harper-ls to a directory with a UTF-8 name and {BINARY_PATH_HINT}"
+            ),
+            Self::BinaryNotFound { path } if path.is_empty() => write!(
+                f,
+                "`lsp.harper-ls.binary.path` is empty. Set it to the harper-ls binary, or remove it to use the managed install"
+            ),
+            Self::BinaryNotFound { path } => write!(
+                f,
+                "`lsp.harper-ls.binary.path` is set to `{path}`, which was not found on PATH. Use an absolute path to harper-ls, or remove the setting to use the managed install"
+            ),
+            Self::NoBinary { order } => write!(
+                f,
+                "No harper-ls binary was found from the binary sources allowed by `lsp.harper-ls.settings.resolution` ({order}). Add `\"managed\"` to let the extension install it, or install harper-ls where one of them finds it"
+            ),
+            Self::BinaryIsDirectory { path } => write!(
+                f,
+                "`lsp.harper-ls.binary.path` points at the directory {path}. Point it at the harper-ls binary inside it"
+            ),
+            Self::ShimTargetMissing {
+                shim,
+                version,
+                target,
+            } => write!(
+                f,
+                "Skipping harper-ls on PATH: {shim} is a version manager shim for version {version}, which is not installed at {target}. Install it with your version manager (e.g. `mise install`)"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSettings(e) => write!(
+                f,
+                "Invalid `lsp.harper-ls` settings: {e}. Fix the `lsp.harper-ls` section of your Zed settings.json"
+            ),
+            Self::Disabled => write!(
+                f,
+                "Harper is disabled for this worktree. Remove `lsp.harper-ls.settings.enabled` or set it to true to enable it"
+            ),
+        }
+    }
+}
+
+impl From<InstallError> for String {
+    fn from(error: InstallError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<ResolveError> for String {
+    fn from(error: ResolveError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<ConfigError> for String {
+    fn from(error: ConfigError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::InstallError;
+use crate::platform::Platform;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Log of install failures in the extension directory, kept when `failure_report` is on.
+pub const FAILURE_LOG: &str = "install-failures.log";
+
+/// Appends an install failure, recording only its category, the platform and the
+/// requested version, so the file can be attached to an issue as-is.
+pub fn record(error: &InstallError, platform: Platform, pinned: Option<&str>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let line = format!(
+        "{timestamp} {} {:?}/{:?} {}\n",
+        error.category(),
+        platform.os,
+        platform.arch,
+        pinned.unwrap_or("latest")
+    );
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(FAILURE_LOG)
+        .and_then(|mut log| log.write_all(line.as_bytes()));
+    if let Err(e) = written {
+        eprintln!("harper-ls: failed to record install failure: {e}");
+    }
+}
+
+pub fn read() -> Option<String> {
+    fs::read_to_string(FAILURE_LOG)
+        .ok()
+        .filter(|log| !log.is_empty())
+}
@@ -0,0 +1,617 @@
+use crate::error::InstallError;
+use crate::{
+    failures, manifest, path_str, platform, release_cache, release_tag, server_log, updates,
+};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use zed_extension_api::{
+    self as zed,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    serde_json::{self, Value},
+};
+
+/// Where releases come from and how their assets are put in place.
+///
+/// Installs go through this trait so that other backends, such as mirrors or local
+/// shares, can stand in for GitHub, and so tests can use a fake.
+pub trait Installer {
+    /// Looks up the release to install: the pinned one by its tag, otherwise the latest.
+    fn release(&self, repo: &str, pinned: Option<&str>)
+    -> Result<zed::GithubRelease, InstallError>;
+
+    /// Looks up a release like [`Installer::release`], without updating any cache the
+    /// backend keeps, for commands that only report what would happen.
+    fn preview_release(
+        &self,
+        repo: &str,
+        pinned: Option<&str>,
+    ) -> Result<zed::GithubRelease, InstallError> {
+        self.release(repo, pinned)
+    }
+
+    /// Downloads and extracts a release asset into `version_dir`, leaving an executable at
+    /// `binary_path`.
+    fn download(
+        &self,
+        asset: &zed::GithubReleaseAsset,
+        version: &str,
+        version_dir: &str,
+        binary_path: &Path,
+    ) -> Result<(), InstallError>;
+
+    /// Returns the Markdown release notes of `version`, if the backend publishes any.
+    fn release_notes(&self, _repo: &str, _version: &str) -> Option<String> {
+        None
+    }
+
+    /// Returns the file `path` of the release's source tree, if the backend can fetch it.
+    fn source_file(&self, _repo: &str, _version: &str, _path: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Suffix of the directory an install is extracted into before it is moved into place.
+pub const STAGING_SUFFIX: &str = ".partial";
+
+/// File written to check that the extension directory has room before a download.
+const SPACE_CHECK_FILE: &str = ".space-check";
+/// How hosts word a full disk in download and extraction errors.
+const OUT_OF_SPACE_ERRORS: &[&str] = &["no space left", "os error 28", "enospc", "disk full"];
+
+/// Name of the release asset's own URL in the `mirrors` option.
+const GITHUB_SOURCE: &str = "github";
+
+/// Returns the asset as published by each of the `mirrors`, in order; just the asset when
+/// there are none. Mirrors are URL templates with `{version}` and `{asset}` placeholders,
+/// or base URLs that `<tag>/<asset>` is appended to.
+pub fn download_sources(
+    asset: &zed::GithubReleaseAsset,
+    version: &str,
+    mirrors: &[String],
+) -> Vec<zed::GithubReleaseAsset> {
+    if mirrors.is_empty() {
+        return vec![asset.clone()];
+    }
+
+    mirrors
+        .iter()
+        .map(|mirror| {
+            let download_url = if mirror == GITHUB_SOURCE {
+                asset.download_url.clone()
+            } else if mirror.contains("{asset}") {
+                mirror
+                    .replace("{version}", &release_tag(version))
+                    .replace("{asset}", &asset.name)
+            } else {
+                format!(
+                    "{}/{}/{}",
+                    mirror.trim_end_matches('/'),
+                    release_tag(version),
+                    asset.name
+                )
+            };
+            zed::GithubReleaseAsset {
+                name: asset.name.clone(),
+                download_url,
+            }
+        })
+        .collect()
+}
+
+/// Downloads the asset into a staging directory and renames it to `version_dir` only
+/// after `verify` accepts the extracted binary, so an interrupted or broken install never
+/// leaves a directory that looks complete. `sources` are tried in order until one
+/// succeeds. Returns the installed binary and the URL it came from.
+pub fn install_staged(
+    installer: &dyn Installer,
+    sources: &[zed::GithubReleaseAsset],
+    version: &str,
+    version_dir: &str,
+    binary_name: &str,
+    verify: impl Fn(&Path) -> Result<(), InstallError>,
+) -> Result<(PathBuf, String), InstallError> {
+    let staging_dir = format!("{version_dir}{STAGING_SUFFIX}");
+    let staged_binary = Path::new(&staging_dir).join(binary_name);
+
+    let mut error = None;
+    for asset in sources {
+        // Left over from an interrupted install or a failed source.
+        fs::remove_dir_all(&staging_dir).ok();
+
+        let staged = ensure_space(asset)
+            .and_then(|()| {
+                installer
+                    .download(asset, version, &staging_dir, &staged_binary)
+                    .map_err(|e| out_of_space(asset, e))
+            })
+            .and_then(|()| verify(&staged_binary))
+            .and_then(|()| {
+                fs::remove_dir_all(version_dir).ok();
+                fs::rename(&staging_dir, version_dir).map_err(|e| InstallError::Staging {
+                    path: version_dir.to_string(),
+                    error: e.to_string(),
+                })
+            });
+        match staged {
+            Ok(()) => {
+                return Ok((
+                    Path::new(version_dir).join(binary_name),
+                    asset.download_url.clone(),
+                ));
+            }
+            // Other sources need the same space; free what this one left behind.
+            Err(e @ InstallError::InsufficientSpace { .. }) => {
+                fs::remove_dir_all(&staging_dir).ok();
+                return Err(e);
+            }
+            Err(e) => {
+                eprintln!("harper-ls: install from {} failed: {e}", asset.download_url);
+                error = Some(e);
+            }
+        }
+    }
+
+    fs::remove_dir_all(&staging_dir).ok();
+    Err(error.unwrap_or_else(|| InstallError::AssetNotFound {
+        target: version_dir.to_string(),
+    }))
+}
+
+/// Fails early when the extension directory is already full. WASI has no way to query
+/// free space, so a small probe file is written and removed again; a disk that fills up
+/// during the download is caught by [`out_of_space`] instead.
+fn ensure_space(asset: &zed::GithubReleaseAsset) -> Result<(), InstallError> {
+    static PROBE: [u8; 64 * 1024] = [0; 64 * 1024];
+
+    let probed = File::create(SPACE_CHECK_FILE)
+        .and_then(|mut file| file.write_all(&PROBE).and_then(|()| file.sync_all()));
+    fs::remove_file(SPACE_CHECK_FILE).ok();
+
+    match probed {
+        Err(e) if e.kind() == io::ErrorKind::StorageFull => Err(InstallError::InsufficientSpace {
+            asset: asset.name.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reports a download or extraction that ran out of disk space as such, since Zed only
+/// passes the host's error message on.
+fn out_of_space(asset: &zed::GithubReleaseAsset, error: InstallError) -> InstallError {
+    let message = error.to_string().to_lowercase();
+    if OUT_OF_SPACE_ERRORS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+    {
+        InstallError::InsufficientSpace {
+            asset: asset.name.clone(),
+        }
+    } else {
+        error
+    }
+}
+
+/// Installs releases published on GitHub, through Zed's download support.
+///
+/// Zed streams downloads to disk and extracts them on the host, so archives never pass
+/// through the extension's WebAssembly memory; only small API responses are fetched into
+/// it. Keep it that way rather than fetching assets with the HTTP client.
+pub struct GithubInstaller;
+
+/// Number of release note entries shown after an upgrade.
+const NOTES_SUMMARY_LEN: usize = 5;
+
+impl GithubInstaller {
+    /// Looks up the pinned or latest release, storing the latest in the release cache
+    /// only when `store` is set.
+    fn lookup(
+        repo: &str,
+        pinned: Option<&str>,
+        store: bool,
+    ) -> Result<zed::GithubRelease, InstallError> {
+        match pinned {
+            Some(pinned) => {
+                zed::github_release_by_tag_name(repo, &release_tag(pinned)).map_err(|error| {
+                    InstallError::PinnedReleaseNotFound {
+                        pinned: pinned.to_string(),
+                        error,
+                    }
+                })
+            }
+            None => release_cache::latest(repo, store)
+                .map(Ok)
+                .unwrap_or_else(|| {
+                    zed::latest_github_release(
+                        repo,
+                        zed::GithubReleaseOptions {
+                            require_assets: true,
+                            pre_release: false,
+                        },
+                    )
+                    .map_err(InstallError::ReleaseLookup)
+                }),
+        }
+    }
+}
+
+impl Installer for GithubInstaller {
+    fn release(
+        &self,
+        repo: &str,
+        pinned: Option<&str>,
+    ) -> Result<zed::GithubRelease, InstallError> {
+        Self::lookup(repo, pinned, true)
+    }
+
+    fn preview_release(
+        &self,
+        repo: &str,
+        pinned: Option<&str>,
+    ) -> Result<zed::GithubRelease, InstallError> {
+        Self::lookup(repo, pinned, false)
+    }
+
+    fn download(
+        &self,
+        asset: &zed::GithubReleaseAsset,
+        version: &str,
+        version_dir: &str,
+        binary_path: &Path,
+    ) -> Result<(), InstallError> {
+        let file_type = platform::archive_type(&asset.name).ok_or_else(|| {
+            InstallError::UnsupportedArchive {
+                asset: asset.name.clone(),
+            }
+        })?;
+        // Uncompressed and gzipped assets are the binary itself rather than a directory of files.
+        let single_file = matches!(
+            file_type,
+            zed::DownloadedFileType::Uncompressed | zed::DownloadedFileType::Gzip
+        );
+        let destination = if single_file {
+            path_str(binary_path).map_err(|e| InstallError::Staging {
+                path: binary_path.display().to_string(),
+                error: e.to_string(),
+            })?
+        } else {
+            version_dir
+        };
+
+        // A missing binary after extraction is retried once with a fresh download.
+        for attempt in 1..=2 {
+            if single_file {
+                fs::create_dir_all(version_dir).map_err(|e| InstallError::Staging {
+                    path: version_dir.to_string(),
+                    error: e.to_string(),
+                })?;
+            }
+            let before = extension_entries();
+            zed::download_file(&asset.download_url, destination, file_type).map_err(|error| {
+                InstallError::Download {
+                    asset: asset.name.clone(),
+                    url: asset.download_url.clone(),
+                    version: version.to_string(),
+                    error,
+                }
+            })?;
+            check_extracted(asset, version_dir, &before)?;
+
+            if binary_path.exists() || normalize_layout(Path::new(version_dir), binary_path) {
+                break;
+            }
+
+            fs::remove_dir_all(version_dir).ok();
+            if attempt == 2 {
+                return Err(InstallError::BinaryMissing {
+                    asset: asset.name.clone(),
+                    expected: binary_path.display().to_string(),
+                });
+            }
+        }
+
+        let path =
+            path_str(binary_path).map_err(|e| InstallError::MakeExecutable(e.to_string()))?;
+        zed::make_file_executable(path).map_err(InstallError::MakeExecutable)?;
+
+        Ok(())
+    }
+
+    fn release_notes(&self, repo: &str, version: &str) -> Option<String> {
+        let response = HttpRequest::builder()
+            .method(HttpMethod::Get)
+            .url(format!(
+                "https://api.github.com/repos/{repo}/releases/tags/{}",
+                release_tag(version)
+            ))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "zed-harper")
+            .redirect_policy(RedirectPolicy::FollowAll)
+            .build()
+            .ok()?
+            .fetch()
+            .ok()?;
+
+        let release: Value = serde_json::from_slice(&response.body).ok()?;
+        release.get("body")?.as_str().map(str::to_string)
+    }
+
+    fn source_file(&self, repo: &str, version: &str, path: &str) -> Option<String> {
+        let response = HttpRequest::builder()
+            .method(HttpMethod::Get)
+            .url(format!(
+                "https://raw.githubusercontent.com/{repo}/{}/{path}",
+                release_tag(version)
+            ))
+            .header("User-Agent", "zed-harper")
+            .redirect_policy(RedirectPolicy::FollowAll)
+            .build()
+            .ok()?
+            .fetch()
+            .ok()?;
+
+        String::from_utf8(response.body).ok()
+    }
+}
+
+/// Picks the first entries of a release's Markdown notes, without the author and pull
+/// request links GitHub appends to generated notes.
+pub fn summarize_notes(body: &str) -> Vec<String> {
+    body.lines()
+        .filter_map(|line| {
+            let entry = line.trim().strip_prefix(['-', '*'])?.trim();
+            let entry = entry.split_once(" by @").map_or(entry, |(entry, _)| entry);
+            (!entry.is_empty()).then(|| entry.to_string())
+        })
+        .take(NOTES_SUMMARY_LEN)
+        .collect()
+}
+
+/// Returns the top-level entries of the extension directory.
+fn extension_entries() -> HashSet<OsString> {
+    fs::read_dir(".")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name())
+        .collect()
+}
+
+/// Whether a file in the extension directory is one the extension or a running server
+/// writes at any time, so it may appear during an extraction without coming from it.
+fn is_own_file(name: &OsString) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    [
+        manifest::MANIFEST_FILE,
+        release_cache::RELEASE_CACHE_FILE,
+        updates::LAST_CHECK_FILE,
+        failures::FAILURE_LOG,
+        SPACE_CHECK_FILE,
+    ]
+    .contains(&name)
+        || name.starts_with(server_log::STDERR_LOG)
+        || name.starts_with(server_log::EXIT_FILE_PREFIX)
+}
+
+/// Guards against archives whose entries escape `version_dir` ("zip slip"): anything
+/// that appeared next to it during extraction, other than [`is_own_file`]s, and symlinks pointing outside of it, are removed
+/// and the install fails.
+///
+/// Zed extracts archives itself, so entries cannot be checked beforehand, and writes
+/// outside the extension directory cannot be seen at all.
+fn check_extracted(
+    asset: &zed::GithubReleaseAsset,
+    version_dir: &str,
+    before: &HashSet<OsString>,
+) -> Result<(), InstallError> {
+    let unsafe_archive = |entry: &Path| InstallError::UnsafeArchive {
+        asset: asset.name.clone(),
+        entry: entry.display().to_string(),
+    };
+
+    for name in extension_entries() {
+        if !before.contains(&name) && name != version_dir && !is_own_file(&name) {
+            let path = PathBuf::from(&name);
+            fs::remove_dir_all(&path)
+                .or_else(|_| fs::remove_file(&path))
+                .ok();
+            return Err(unsafe_archive(&path));
+        }
+    }
+
+    fn escaping_link(dir: &Path, depth: usize) -> Option<PathBuf> {
+        for entry in fs::read_dir(dir).ok()?.flatten() {
+            let path = entry.path();
+            let file_type = entry.file_type().ok()?;
+            if file_type.is_symlink() {
+                let target = fs::read_link(&path).ok()?;
+                let escapes =
+                    target.is_absolute() || target.components().any(|c| c == Component::ParentDir);
+                if escapes {
+                    return Some(path);
+                }
+            } else if file_type.is_dir()
+                && depth > 0
+                && let Some(found) = escaping_link(&path, depth - 1)
+            {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    match escaping_link(Path::new(version_dir), 8) {
+        Some(link) => Err(unsafe_archive(&link)),
+        None => Ok(()),
+    }
+}
+
+/// Moves a binary extracted into a nested directory (e.g. `harper-ls-<target>/harper-ls`)
+/// to where the extension expects it. Returns whether the binary is now in place.
+fn normalize_layout(version_dir: &Path, binary_path: &Path) -> bool {
+    let Some(file_name) = binary_path.file_name() else {
+        return false;
+    };
+
+    fn find(dir: &Path, file_name: &std::ffi::OsStr, depth: usize) -> Option<PathBuf> {
+        let entries = fs::read_dir(dir).ok()?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if depth > 0
+                    && let Some(found) = find(&path, file_name, depth - 1)
+                {
+                    return Some(found);
+                }
+            } else if path.file_name() == Some(file_name) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    find(version_dir, file_name, 3).is_some_and(|nested| fs::rename(nested, binary_path).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Serves a fixed release and "downloads" assets by writing a stand-in binary,
+    /// failing for the URLs in `failing`.
+    #[derive(Default)]
+    struct FakeInstaller {
+        assets: Vec<zed::GithubReleaseAsset>,
+        failing: Vec<(&'static str, &'static str)>,
+        downloads: RefCell<Vec<String>>,
+    }
+
+    impl Installer for FakeInstaller {
+        fn release(
+            &self,
+            _repo: &str,
+            pinned: Option<&str>,
+        ) -> Result<zed::GithubRelease, InstallError> {
+            Ok(zed::GithubRelease {
+                version: pinned.unwrap_or("v0.36.0").to_string(),
+                assets: self.assets.clone(),
+            })
+        }
+
+        fn download(
+            &self,
+            asset: &zed::GithubReleaseAsset,
+            version: &str,
+            version_dir: &str,
+            binary_path: &Path,
+        ) -> Result<(), InstallError> {
+            self.downloads.borrow_mut().push(asset.download_url.clone());
+            if let Some((_, error)) = self
+                .failing
+                .iter()
+                .find(|(url, _)| *url == asset.download_url)
+            {
+                return Err(InstallError::Download {
+                    asset: asset.name.clone(),
+                    url: asset.download_url.clone(),
+                    version: version.to_string(),
+                    error: error.to_string(),
+                });
+            }
+            fs::create_dir_all(version_dir).unwrap();
+            fs::write(binary_path, asset.download_url.as_bytes()).unwrap();
+            Ok(())
+        }
+    }
+
+    fn asset(url: &str) -> zed::GithubReleaseAsset {
+        zed::GithubReleaseAsset {
+            name: "harper-ls-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+            download_url: url.to_string(),
+        }
+    }
+
+    /// A fresh version directory under the temporary directory.
+    fn version_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("zed-harper-install-{name}"));
+        fs::remove_dir_all(&dir).ok();
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn install_staged_falls_back_to_the_next_source() {
+        let installer = FakeInstaller {
+            failing: vec![("https://mirror.example/a", "connection refused")],
+            ..FakeInstaller::default()
+        };
+        let sources = [
+            asset("https://mirror.example/a"),
+            asset("https://github.com/a"),
+        ];
+        let dir = version_dir("fallback");
+
+        let (binary, url) =
+            install_staged(&installer, &sources, "v0.36.0", &dir, "harper-ls", |_| {
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(url, "https://github.com/a");
+        assert_eq!(fs::read_to_string(binary).unwrap(), "https://github.com/a");
+        assert_eq!(installer.downloads.borrow().len(), 2);
+        assert!(!Path::new(&format!("{dir}{STAGING_SUFFIX}")).exists());
+    }
+
+    #[test]
+    fn install_staged_keeps_nothing_that_fails_verification() {
+        let installer = FakeInstaller::default();
+        let dir = version_dir("rejected");
+
+        let result = install_staged(
+            &installer,
+            &[asset("https://github.com/a")],
+            "v0.36.0",
+            &dir,
+            "harper-ls",
+            |_| {
+                Err(InstallError::WrongTarget {
+                    asset: "harper-ls".to_string(),
+                    expected: "x86_64-unknown-linux-gnu".to_string(),
+                    found: "an aarch64-linux executable".to_string(),
+                })
+            },
+        );
+        assert!(matches!(result, Err(InstallError::WrongTarget { .. })));
+        assert!(!Path::new(&dir).exists());
+        assert!(!Path::new(&format!("{dir}{STAGING_SUFFIX}")).exists());
+    }
+
+    #[test]
+    fn install_staged_stops_when_the_disk_is_full() {
+        let installer = FakeInstaller {
+            failing: vec![(
+                "https://github.com/a",
+                "failed to write: No space left on device (os error 28)",
+            )],
+            ..FakeInstaller::default()
+        };
+        let sources = [
+            asset("https://github.com/a"),
+            asset("https://mirror.example/a"),
+        ];
+        let dir = version_dir("full");
+
+        let result = install_staged(&installer, &sources, "v0.36.0", &dir, "harper-ls", |_| {
+            Ok(())
+        });
+        assert!(matches!(
+            result,
+            Err(InstallError::InsufficientSpace { .. })
+        ));
+        assert_eq!(installer.downloads.borrow().len(), 1);
+    }
+}
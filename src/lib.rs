@@ -1,109 +1,659 @@
+mod cli;
+mod commands;
+mod config;
+mod devcontainer;
+mod dictionary;
+mod error;
+mod failures;
+mod install;
+mod linters;
+mod manifest;
+mod nix;
+mod platform;
+mod presets;
+mod release_cache;
+mod report;
+mod restart;
+mod sandbox;
+mod schema;
+mod server_log;
+mod settings;
+mod sha256;
+mod shims;
+mod stats;
+mod updates;
+mod wsl;
+
+use error::{ConfigError, InstallError, ResolveError};
+use install::{GithubInstaller, Installer};
+use platform::Platform;
+use restart::RestartTracker;
+use settings::ExtensionOptions;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use updates::Cadence;
 use zed::Command;
-use zed_extension_api::{self as zed, Result, serde_json::json, settings::LspSettings};
+use zed_extension_api::{self as zed, Result, settings::LspSettings};
 
 static NAME: &str = "harper-ls";
+const SLASH_COMMAND: &str = "harper";
+/// Environment variable naming a harper-ls binary to launch.
+const BINARY_ENV: &str = "HARPER_LS_PATH";
+/// GitHub repository releases are downloaded from, unless the `repository` option is set.
+const DEFAULT_RELEASE_REPO: &str = "Automattic/harper";
+
+/// Values of the `log_level` option, passed to harper-ls as `RUST_LOG`.
+const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+const STDIO_FLAG: &str = "--stdio";
+/// Flags that select the server transport; when any is present user arguments are used verbatim.
+const TRANSPORT_FLAGS: &[&str] = &[STDIO_FLAG, "--port", "--socket", "--pipe"];
 
 struct HarperExtension {
-    binary_cache: Option<PathBuf>,
+    /// `zed::Extension` requires `Send + Sync`, so the boxed installer has to be too.
+    installer: Box<dyn Installer + Send + Sync>,
+    worktrees: HashMap<u64, WorktreeState>,
+    versions_in_use: HashSet<String>,
+    /// Whether a release check ran in this session.
+    checked_for_update: bool,
+    /// Worktrees silenced by `/harper pause`. Slash commands only get `&self`.
+    paused: Mutex<HashSet<u64>>,
+}
+
+/// State kept per worktree, so projects with different pins or settings stay independent.
+#[derive(Default)]
+struct WorktreeState {
+    binary: Option<PathBuf>,
+    restarts: RestartTracker,
+    /// Version of the last launched server, to send it configuration it understands.
+    server_version: Option<String>,
+    /// Kept until a slash command reports them, which only gets `&self`.
+    messages: Mutex<Messages>,
+}
+
+/// Messages shown by the next `/harper check` (notices) or `/harper diagnose` (warnings).
+#[derive(Default)]
+struct Messages {
+    warnings: Vec<String>,
+    /// Informational messages, such as what changed in an upgrade.
+    notices: Vec<String>,
+}
+
+/// Messages of each kind kept per worktree; older ones are dropped first.
+const MAX_MESSAGES: usize = 20;
+
+/// Which release to install for a worktree and how it is verified.
+#[derive(Clone, Copy)]
+struct InstallPlan<'a> {
+    repo: &'a str,
+    pinned: Option<&'a str>,
+    cadence: Cadence,
+    /// Expected SHA-256 of the binary, for pinned versions.
+    sha256: Option<&'a str>,
+    mirrors: &'a [String],
 }
 
 #[derive(Clone)]
 struct HarperBinary {
     path: PathBuf,
-    args: Option<Vec<String>>,
+    args: Vec<String>,
     env: Option<Vec<(String, String)>>,
+    source: BinarySource,
+    version: Option<String>,
+}
+
+impl HarperBinary {
+    fn managed(path: PathBuf) -> Self {
+        let version = path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str())
+            .and_then(dir_version)
+            .map(str::to_string);
+
+        Self {
+            path,
+            args: server_args(None),
+            env: None,
+            source: BinarySource::Managed,
+            version,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BinarySource {
+    Settings,
+    Env,
+    Path,
+    Managed,
+    Wsl,
+    DevContainer,
+    Nix,
+    Share,
+}
+
+impl BinarySource {
+    /// The default resolution order.
+    const ALL: [Self; 8] = [
+        Self::Settings,
+        Self::Env,
+        Self::Wsl,
+        Self::DevContainer,
+        Self::Path,
+        Self::Nix,
+        Self::Share,
+        Self::Managed,
+    ];
+
+    /// The name used in the `resolution` option.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Settings => "settings",
+            Self::Env => "env",
+            Self::Path => "path",
+            Self::Managed => "managed",
+            Self::Wsl => "wsl",
+            Self::DevContainer => "devcontainer",
+            Self::Nix => "nix",
+            Self::Share => "share",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Settings => "lsp.harper-ls.binary.path setting",
+            Self::Env => "HARPER_LS_PATH environment variable",
+            Self::Path => "PATH",
+            Self::Managed => "extension-managed install",
+            Self::Wsl => "harper-ls inside WSL",
+            Self::DevContainer => "harper-ls inside the dev container",
+            Self::Nix => "harper-ls provided by Nix",
+            Self::Share => "binary_share_dir",
+        }
+    }
 }
 
 impl HarperExtension {
     fn new() -> Self {
-        Self { binary_cache: None }
+        Self {
+            installer: Box::new(GithubInstaller),
+            worktrees: HashMap::new(),
+            versions_in_use: HashSet::new(),
+            checked_for_update: false,
+            paused: Mutex::default(),
+        }
+    }
+
+    /// Records a non-fatal problem; it is logged and shown by `/harper diagnose`.
+    fn warn(&mut self, worktree_id: u64, message: String) {
+        eprintln!("{NAME}: {message}");
+        let warnings = &mut self.messages_mut(worktree_id).warnings;
+        if !warnings.contains(&message) {
+            push_capped(warnings, message);
+        }
+    }
+
+    fn messages_mut(&mut self, worktree_id: u64) -> &mut Messages {
+        let messages = &mut self.worktrees.entry(worktree_id).or_default().messages;
+        messages.get_mut().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the worktree's warnings and clears them, so each is reported once.
+    fn take_warnings(&self, worktree: &zed::Worktree) -> Vec<String> {
+        self.take_messages(worktree, |messages| &mut messages.warnings)
+    }
+
+    /// Returns the worktree's notices and clears them, so each is reported once.
+    fn take_notices(&self, worktree: &zed::Worktree) -> Vec<String> {
+        self.take_messages(worktree, |messages| &mut messages.notices)
+    }
+
+    fn take_messages(
+        &self,
+        worktree: &zed::Worktree,
+        kind: fn(&mut Messages) -> &mut Vec<String>,
+    ) -> Vec<String> {
+        self.worktrees
+            .get(&worktree.id())
+            .and_then(|state| state.messages.lock().ok())
+            .map(|mut messages| std::mem::take(kind(&mut messages)))
+            .unwrap_or_default()
+    }
+
+    fn is_paused(&self, worktree: &zed::Worktree) -> bool {
+        self.paused
+            .lock()
+            .is_ok_and(|paused| paused.contains(&worktree.id()))
+    }
+
+    fn set_paused(&self, worktree: &zed::Worktree, paused: bool) {
+        if let Ok(mut worktrees) = self.paused.lock() {
+            if paused {
+                worktrees.insert(worktree.id());
+            } else {
+                worktrees.remove(&worktree.id());
+            }
+        }
+    }
+
+    fn in_safe_mode(&self, worktree: &zed::Worktree) -> bool {
+        self.worktrees
+            .get(&worktree.id())
+            .is_some_and(|state| state.restarts.in_safe_mode())
+    }
+
+    /// Clears the worktree's crash count; returns whether there was one to clear.
+    fn reset_crashes(&self, worktree: &zed::Worktree) -> bool {
+        self.worktrees
+            .get(&worktree.id())
+            .filter(|state| state.restarts.crashes() > 0)
+            .inspect(|state| state.restarts.reset())
+            .is_some()
     }
 
     fn get_binary(
         &mut self,
         language_server_id: &zed::LanguageServerId,
         worktree: &zed::Worktree,
+        options: &ExtensionOptions,
     ) -> Result<HarperBinary> {
-        let binary = LspSettings::for_worktree(NAME, worktree)
-            .ok()
-            .and_then(|lsp_settings| lsp_settings.binary)
-            .and_then(|binary| binary.path.map(|path| (path, binary.arguments.clone())));
-
-        if let Some((path, args)) = binary {
-            return Ok(HarperBinary {
-                path: PathBuf::from(path),
-                args,
-                env: Some(worktree.shell_env()),
-            });
+        let pinned = options.version.clone();
+
+        let mut warnings = Vec::new();
+        resolution_order(&options.resolution, &mut warnings);
+        let cadence = Cadence::parse(options.update_check.as_deref(), &mut warnings);
+        if let Some(level) = &options.log_level
+            && !LOG_LEVELS.contains(&level.as_str())
+        {
+            warnings.push(format!(
+                "Unknown log_level `{level}` ignored; use one of {}",
+                LOG_LEVELS.join(", ")
+            ));
+        }
+        if let Some(repo) = &options.repository
+            && !config::is_repo(repo)
+        {
+            warnings.push(format!(
+                "Invalid repository `{repo}` ignored; use the `owner/name` form, e.g. `{DEFAULT_RELEASE_REPO}`"
+            ));
+        }
+        if options.expected_sha256.is_some() && pinned.is_none() {
+            warnings.push(
+                "expected_sha256 is ignored without a pinned version; set `version` to the release the digest belongs to".to_string(),
+            );
+        }
+        for warning in warnings {
+            self.warn(worktree.id(), warning);
         }
 
-        if let Some(path) = worktree.which(NAME) {
-            return Ok(HarperBinary {
-                path: PathBuf::from(path),
-                args: None,
-                env: Some(worktree.shell_env()),
-            });
+        let mut skipped = Vec::new();
+        let found = self.find_binary_skipping(worktree, options, &mut skipped);
+        for reason in skipped {
+            self.warn(worktree.id(), reason);
+        }
+        if let Some(binary) = found? {
+            if binary.source == BinarySource::Path && shims::is_shim(&binary.path.to_string_lossy())
+            {
+                self.warn(worktree.id(), format!(
+                    "{} is a version manager shim whose harper-ls version is not pinned in .tool-versions or mise.toml; it fails if the plugin is not installed",
+                    binary.path.display()
+                ));
+            }
+            if binary.source == BinarySource::Path
+                && let Some(pinned) = &pinned
+                && let Some(found) = binary
+                    .version
+                    .as_deref()
+                    .and_then(parse_version)
+                    .or_else(|| path_version(&binary.path))
+                && parse_version(pinned).is_some_and(|pinned| pinned != found)
+            {
+                self.warn(worktree.id(), format!(
+                    "{} from PATH is used instead of pinned version {pinned}, because a harper-ls on PATH takes precedence over managed installs. Remove it from PATH or set lsp.harper-ls.binary.path",
+                    binary.path.display()
+                ));
+            }
+            // Batch wrappers on Windows are launched as `cmd.exe /C <script>`.
+            let launched = match binary.args.as_slice() {
+                [flag, script, ..] if flag == "/C" => Path::new(script),
+                _ => binary.path.as_path(),
+            };
+            if binary.source == BinarySource::Settings
+                && !launched
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains("harper"))
+            {
+                self.warn(worktree.id(), format!(
+                    "lsp.harper-ls.binary.path {} does not look like harper-ls; if the server fails to start, check that it points at the harper-ls binary",
+                    binary.path.display()
+                ));
+            }
+            return Ok(binary);
         }
 
-        if let Some(path) = &self.binary_cache
-            && path.exists()
-        {
-            return Ok(HarperBinary {
-                path: path.clone(),
-                args: None,
-                env: None,
-            });
+        let env = worktree.shell_env();
+        if nix::is_nixos(&env) {
+            return Err(InstallError::NixOs.into());
         }
 
-        self.install_binary(language_server_id)
+        let host = Platform::for_worktree(worktree);
+        if host != Platform::current() {
+            self.warn(worktree.id(), format!(
+                "The worktree host reports {:?} / {:?}, which differs from the extension's platform; installing harper-ls for the worktree host",
+                host.os, host.arch
+            ));
+        }
+
+        let repo = config::release_repo(options);
+        let plan = InstallPlan {
+            repo: &repo,
+            pinned: pinned.as_deref(),
+            cadence,
+            sha256: pinned.as_ref().and(options.expected_sha256.as_deref()),
+            mirrors: &options.mirrors,
+        };
+        self.install_binary(language_server_id, worktree.id(), plan, host)
+            .inspect_err(|e| {
+                if options.failure_report {
+                    failures::record(e, host, pinned.as_deref());
+                }
+            })
+            .map_err(String::from)
+    }
+
+    /// Tries the binary sources in the configured resolution order. Returns `None` when
+    /// the managed install is reached without a cached binary, meaning one should be
+    /// installed.
+    fn find_binary(
+        &self,
+        worktree: &zed::Worktree,
+        options: &ExtensionOptions,
+    ) -> Result<Option<HarperBinary>> {
+        self.find_binary_skipping(worktree, options, &mut Vec::new())
+    }
+
+    /// Like [`Self::find_binary`], pushing why shared binaries that failed verification
+    /// were skipped onto `skipped`.
+    fn find_binary_skipping(
+        &self,
+        worktree: &zed::Worktree,
+        options: &ExtensionOptions,
+        skipped: &mut Vec<String>,
+    ) -> Result<Option<HarperBinary>> {
+        let order = resolution_order(&options.resolution, &mut Vec::new());
+        for &source in &order {
+            if source == BinarySource::Managed {
+                return Ok(self.cached_binary(worktree, options));
+            }
+            match self.find_in(source, worktree, options) {
+                Ok(Some(mut binary)) => {
+                    if Platform::for_worktree(worktree).os == zed::Os::Windows {
+                        shims::windows_launcher(&mut binary.path, &mut binary.args);
+                    }
+                    return Ok(Some(binary));
+                }
+                Ok(None) => {}
+                // A share that cannot be verified, or a shim whose pinned version is not
+                // installed, is never launched; the next source is.
+                Err(e) if matches!(source, BinarySource::Share | BinarySource::Path) => {
+                    skipped.push(e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(ResolveError::NoBinary {
+            order: order
+                .iter()
+                .map(|source| source.name())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+        .into())
+    }
+
+    fn find_in(
+        &self,
+        source: BinarySource,
+        worktree: &zed::Worktree,
+        options: &ExtensionOptions,
+    ) -> Result<Option<HarperBinary>> {
+        let wsl_distro = wsl::distro(worktree);
+
+        match source {
+            BinarySource::Settings => {
+                let Some((path, args)) = LspSettings::for_worktree(NAME, worktree)
+                    .map_err(ConfigError::InvalidSettings)?
+                    .binary
+                    .and_then(|binary| binary.path.map(|path| (path, binary.arguments.clone())))
+                else {
+                    return Ok(None);
+                };
+
+                // A Linux path in a WSL worktree refers to a binary inside the distribution.
+                if let Some(distro) = &wsl_distro
+                    && path.starts_with('/')
+                {
+                    return Ok(Some(wsl::binary(distro, &path, args)));
+                }
+
+                Ok(Some(HarperBinary {
+                    path: validate_binary_path(worktree, &path)?,
+                    args: server_args(args),
+                    env: Some(worktree.shell_env()),
+                    source,
+                    version: None,
+                }))
+            }
+            BinarySource::Env => {
+                let env = worktree.shell_env();
+                let Some(path) = env_var(&env, BINARY_ENV) else {
+                    return Ok(None);
+                };
+
+                Ok(Some(HarperBinary {
+                    path: validate_binary_path(worktree, path)?,
+                    args: server_args(None),
+                    env: Some(worktree.shell_env()),
+                    source,
+                    version: None,
+                }))
+            }
+            BinarySource::Wsl => Ok(wsl_distro.and_then(|distro| {
+                wsl::find(worktree, &distro, NAME).map(|path| wsl::binary(&distro, &path, None))
+            })),
+            BinarySource::DevContainer => Ok(options
+                .devcontainer
+                .then(|| devcontainer::binary(worktree))
+                .flatten()),
+            BinarySource::Path => {
+                let os = Platform::for_worktree(worktree).os;
+                let Some(path) = shims::which(worktree, NAME, os) else {
+                    return Ok(None);
+                };
+
+                // Version managers put shims on PATH; launch the binary they point at when known.
+                let (path, version) = if shims::is_shim(&path) {
+                    shims::resolve(worktree, &path)?
+                        .map(|(path, version)| (path, Some(version)))
+                        .unwrap_or((PathBuf::from(path), None))
+                } else {
+                    (PathBuf::from(path), None)
+                };
+
+                Ok(Some(HarperBinary {
+                    path,
+                    args: server_args(None),
+                    env: Some(worktree.shell_env()),
+                    source,
+                    version,
+                }))
+            }
+            BinarySource::Nix => Ok(nix::binary(worktree)),
+            BinarySource::Share => {
+                let Some(binary) = shared_binary(worktree, options) else {
+                    return Ok(None);
+                };
+                if let Some(expected) = options
+                    .version
+                    .as_ref()
+                    .and(options.expected_sha256.as_ref())
+                {
+                    // Shares outside the extension's sandbox cannot be read, so not verified.
+                    if fs::File::open(&binary.path).is_err() {
+                        return Err(InstallError::Unverifiable {
+                            binary: binary.path.display().to_string(),
+                        }
+                        .into());
+                    }
+                    sha256::verify(&binary.path, expected).map_err(|found| {
+                        InstallError::DigestMismatch {
+                            binary: binary.path.display().to_string(),
+                            expected: expected.clone(),
+                            found,
+                        }
+                    })?;
+                }
+                Ok(Some(binary))
+            }
+            BinarySource::Managed => Ok(self.cached_binary(worktree, options)),
+        }
+    }
+
+    /// The managed binary this worktree last launched, if it still matches the pin.
+    fn cached_binary(
+        &self,
+        worktree: &zed::Worktree,
+        options: &ExtensionOptions,
+    ) -> Option<HarperBinary> {
+        self.worktrees
+            .get(&worktree.id())
+            .and_then(|state| state.binary.as_ref())
+            .filter(|path| {
+                path.exists()
+                    && options
+                        .version
+                        .as_ref()
+                        .is_none_or(|pinned| is_version_dir(path.parent().unwrap_or(path), pinned))
+            })
+            .map(|path| HarperBinary::managed(path.clone()))
     }
 
     fn install_binary(
         &mut self,
         language_server_id: &zed::LanguageServerId,
-    ) -> Result<HarperBinary> {
+        worktree_id: u64,
+        plan: InstallPlan,
+        platform: Platform,
+    ) -> Result<HarperBinary, InstallError> {
+        let InstallPlan {
+            repo,
+            pinned,
+            cadence,
+            sha256,
+            mirrors,
+        } = plan;
+
+        if let Some(pinned) = pinned
+            && let Some((dir, binary_path)) = installed_versions(platform)
+                .into_iter()
+                .find(|(dir, _)| is_version_dir(Path::new(dir), pinned))
+        {
+            match sha256.map(|expected| sha256::verify(&binary_path, expected)) {
+                Some(Err(found)) => {
+                    self.warn(worktree_id, format!(
+                        "Installed {dir} does not match expected_sha256 (found {found}); reinstalling it"
+                    ));
+                    fs::remove_dir_all(&dir).ok();
+                }
+                _ => return Ok(self.use_managed(worktree_id, binary_path)),
+            }
+        }
+
+        let checked = match cadence {
+            Cadence::Worktree => false,
+            Cadence::Session => self.checked_for_update,
+            Cadence::Daily => updates::checked_today(),
+        };
+        if pinned.is_none()
+            && checked
+            && let Some((_, binary_path)) = installed_versions(platform).into_iter().next()
+        {
+            return Ok(self.use_managed(worktree_id, binary_path));
+        }
+
         zed::set_language_server_installation_status(
             language_server_id,
             &zed::LanguageServerInstallationStatus::CheckingForUpdate,
         );
 
-        let release = zed::latest_github_release(
-            "elijah-potter/harper",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )
-        .map_err(|e| format!("Failed to fetch latest release: {e}"))?;
+        let release = match self.installer.release(repo, pinned) {
+            Ok(release) => {
+                self.checked_for_update = true;
+                updates::record_check();
+                release
+            }
+            Err(InstallError::ReleaseLookup(e)) => {
+                let Some((version_dir, binary_path)) =
+                    installed_versions(platform).into_iter().next()
+                else {
+                    return Err(InstallError::ReleaseLookup(e));
+                };
 
-        let (platform, arch) = zed::current_platform();
-        let arch_name = match arch {
-            zed::Architecture::Aarch64 => "aarch64",
-            zed::Architecture::X8664 => "x86_64",
-            zed::Architecture::X86 => return Err("x86 architecture is not supported".into()),
+                self.warn(
+                    worktree_id,
+                    format!("Failed to check for Harper updates ({e}); using cached {version_dir}"),
+                );
+                return Ok(self.use_managed(worktree_id, binary_path));
+            }
+            Err(e) => return Err(e),
         };
 
-        let (os_str, file_ext) = match platform {
-            zed::Os::Mac => ("apple-darwin", "tar.gz"),
-            zed::Os::Linux => ("unknown-linux-gnu", "tar.gz"),
-            zed::Os::Windows => ("pc-windows-msvc", "zip"),
+        let native = platform.asset_target();
+        let Some((target, asset)) = platform.select_asset(NAME, &release.assets) else {
+            // Few releases have 32-bit builds; building from source is the way out.
+            if platform.arch == zed::Architecture::X86 {
+                return Err(InstallError::UnsupportedArchitecture("x86"));
+            }
+            return Err(InstallError::AssetNotFound {
+                target: native.triple(),
+            });
         };
-
-        let asset_name = format!("{NAME}-{arch_name}-{os_str}.{file_ext}");
-        let asset = release
-            .assets
-            .iter()
-            .find(|a| a.name == asset_name)
-            .ok_or_else(|| format!("No compatible Harper binary found for {arch_name}-{os_str}"))?;
-
-        let version_dir = format!("{NAME}-{}", release.version);
-        let mut binary_path = PathBuf::from(&version_dir).join(NAME);
-
-        if platform == zed::Os::Windows {
-            binary_path.set_extension("exe");
+        if target.triple() != native.triple() {
+            self.notify(worktree_id, format!(
+                "Release {} has no native {} build; installing {}, which runs emulated and slower",
+                release.version,
+                native.triple(),
+                target.triple()
+            ));
+        }
+        if platform.arch == zed::Architecture::X86 {
+            self.notify(
+                worktree_id,
+                format!(
+                    "Installing the 32-bit {} build {}",
+                    target.triple(),
+                    asset.name
+                ),
+            );
         }
+        if asset.name != target.asset_name() {
+            self.warn(
+                worktree_id,
+                format!(
+                    "Release {} has no {} asset; using {} instead",
+                    release.version,
+                    target.asset_name(),
+                    asset.name
+                ),
+            );
+        }
+
+        let version_dir = target.version_dir(NAME, &release.version);
+        let binary_path = PathBuf::from(&version_dir).join(platform.binary_file_name());
 
         if !binary_path.exists() {
             zed::set_language_server_installation_status(
@@ -111,47 +661,142 @@ impl HarperExtension {
                 &zed::LanguageServerInstallationStatus::Downloading,
             );
 
-            let download_result = (|| -> Result<()> {
-                zed::download_file(
-                    &asset.download_url,
-                    &version_dir,
-                    if platform == zed::Os::Windows {
-                        zed::DownloadedFileType::Zip
-                    } else {
-                        zed::DownloadedFileType::GzipTar
-                    },
-                )
-                .map_err(|e| format!("Failed to download Harper binary: {e}"))?;
-
-                zed::make_file_executable(binary_path.to_str().ok_or("Invalid binary path")?)
-                    .map_err(|e| format!("Failed to make binary executable: {e}"))?;
+            let (_, source) = install::install_staged(
+                self.installer.as_ref(),
+                &install::download_sources(asset, &release.version, mirrors),
+                &release.version,
+                &version_dir,
+                platform.binary_file_name(),
+                |binary| {
+                    target
+                        .check_binary(binary)
+                        .map_err(|found| InstallError::WrongTarget {
+                            asset: asset.name.clone(),
+                            expected: target.triple(),
+                            found,
+                        })?;
+                    match sha256 {
+                        Some(expected) => sha256::verify(binary, expected).map_err(|found| {
+                            InstallError::DigestMismatch {
+                                binary: asset.name.clone(),
+                                expected: expected.to_string(),
+                                found,
+                            }
+                        }),
+                        None => Ok(()),
+                    }
+                },
+            )?;
+            let defaults = self
+                .installer
+                .source_file(repo, &release.version, linters::LINT_GROUP_SOURCE)
+                .map(|source| linters::defaults(&source))
+                .unwrap_or_default();
+            manifest::record(manifest::Install {
+                dir: version_dir.clone(),
+                version: release.version.clone(),
+                target: target.triple(),
+                asset: asset.name.clone(),
+                source,
+                rules: defaults.keys().cloned().collect(),
+            });
 
-                Ok(())
-            })();
+            // Keep the newest previous version around as a fallback for crash loops,
+            // versions other worktrees pinned and still run, and the newest install of
+            // each other target, for when Zed runs under another architecture again.
+            let previous = installed_versions(platform)
+                .into_iter()
+                .map(|(dir, _)| dir)
+                .find(|dir| *dir != version_dir);
+            let other_targets = newest_per_target(&target.triple());
 
-            if let Err(e) = download_result {
-                fs::remove_dir_all(&version_dir).ok();
-                return Err(e);
+            if let Some(previous) = &previous {
+                self.announce_upgrade(worktree_id, repo, previous, &release.version, &defaults);
             }
 
             if let Ok(entries) = fs::read_dir(".") {
                 for entry in entries.flatten() {
                     if let Ok(name) = entry.file_name().into_string()
+                        && name.starts_with(&format!("{NAME}-"))
                         && name != version_dir
+                        && previous.as_ref() != Some(&name)
+                        && !other_targets.contains(&name)
+                        && !self.versions_in_use.contains(&name)
                     {
                         fs::remove_dir_all(entry.path()).ok();
                     }
                 }
             }
+            manifest::prune();
         }
 
-        self.binary_cache = Some(binary_path.clone());
+        Ok(self.use_managed(worktree_id, binary_path))
+    }
 
-        Ok(HarperBinary {
-            path: binary_path,
-            args: None,
-            env: None,
-        })
+    /// Tells the user what changed when `version` replaces an older install, since new
+    /// rules make diagnostics appear that were not there before.
+    fn announce_upgrade(
+        &mut self,
+        worktree_id: u64,
+        repo: &str,
+        previous_dir: &str,
+        version: &str,
+        defaults: &BTreeMap<String, bool>,
+    ) {
+        let previous = dir_version(previous_dir).unwrap_or(previous_dir);
+        if parse_version(previous) >= parse_version(version) {
+            return;
+        }
+
+        let mut message = format!("Harper upgraded from {previous} to {version}");
+        let notes = self
+            .installer
+            .release_notes(repo, version)
+            .map(|body| install::summarize_notes(&body))
+            .unwrap_or_default();
+        if notes.is_empty() {
+            message.push_str(&format!(
+                "; see https://github.com/{repo}/releases/tag/{} for what changed",
+                release_tag(version)
+            ));
+        } else {
+            message.push_str(" — notable changes:");
+            for note in notes {
+                message.push_str(&format!("\n  - {note}"));
+            }
+        }
+        self.notify(worktree_id, message);
+
+        let old = self
+            .installer
+            .source_file(repo, previous, linters::LINT_GROUP_SOURCE)
+            .map(|source| linters::defaults(&source))
+            .unwrap_or_default();
+        if !old.is_empty()
+            && !defaults.is_empty()
+            && let enabled = linters::newly_enabled(&old, defaults)
+            && let Some(first) = enabled.first()
+        {
+            self.notify(worktree_id, format!(
+                "Harper {version} enables these rules by default: {}. To turn one off, add it to `linters` in the `harper-ls` settings section, e.g. `\"{first}\": false`",
+                enabled.join(", ")
+            ));
+        }
+    }
+
+    fn notify(&mut self, worktree_id: u64, message: String) {
+        eprintln!("{NAME}: {message}");
+        push_capped(&mut self.messages_mut(worktree_id).notices, message);
+    }
+
+    /// Remembers a managed binary for the worktree, and so that it is not cleaned up while
+    /// in use.
+    fn use_managed(&mut self, worktree_id: u64, binary_path: PathBuf) -> HarperBinary {
+        if let Some(version_dir) = binary_path.parent().and_then(|dir| dir.to_str()) {
+            self.versions_in_use.insert(version_dir.to_string());
+        }
+        self.worktrees.entry(worktree_id).or_default().binary = Some(binary_path.clone());
+        HarperBinary::managed(binary_path)
     }
 }
 
@@ -165,14 +810,136 @@ impl zed::Extension for HarperExtension {
         language_server_id: &zed::LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<Command> {
-        let HarperBinary { path, args, env } = self.get_binary(language_server_id, worktree)?;
+        let options = config::options(worktree);
+        if !options.enabled {
+            return Err(ConfigError::Disabled.into());
+        }
+
+        let platform = Platform::for_worktree(worktree);
+        // The launch wrapper needs `/bin/sh`, so crashes are only tracked outside Windows.
+        let wrapped = platform.os != zed::Os::Windows;
+        let capture_stderr = options.stderr_log && wrapped;
+        if options.stderr_log && !capture_stderr {
+            self.warn(
+                worktree.id(),
+                "stderr_log is not supported on Windows and was ignored".to_string(),
+            );
+        }
+
+        // Only the launch wrapper records how the previous server exited.
+        let previous_exit = wrapped
+            .then(|| server_log::take_exit_status(worktree.id()))
+            .flatten();
+        let restarts = &self.worktrees.entry(worktree.id()).or_default().restarts;
+        let crashes = restarts.register_launch(previous_exit);
+        let backoff = restarts.backoff_secs();
+        if let Some(code) = previous_exit
+            && restarts.in_safe_mode()
+        {
+            let suspects = config::crash_suspects(worktree);
+            let cause = match suspects.split_first() {
+                Some((likely, [])) => {
+                    format!("The likely cause is {likely}.")
+                }
+                Some((likely, others)) => format!(
+                    "The likely cause is {likely}; also check {}.",
+                    others.join(", ")
+                ),
+                None => "No custom configuration is set, so the crash is likely a harper-ls bug; report it upstream.".to_string(),
+            };
+            self.warn(worktree.id(), format!(
+                "harper-ls crashed {crashes} times in a row (last exit status {code}), so it was relaunched in safe mode without your Harper configuration or initialization options. {cause} Fix it, then run `/harper reset-crashes` and restart the language server to leave safe mode."
+            ));
+        }
+
+        let HarperBinary {
+            mut path,
+            args,
+            env,
+            source,
+            mut version,
+        } = self
+            .get_binary(language_server_id, worktree, &options)
+            .inspect_err(|e| {
+                zed::set_language_server_installation_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+                );
+            })?;
+
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::None,
+        );
+
+        if crashes >= restart::FALLBACK_AFTER
+            && source == BinarySource::Managed
+            && let Some(previous) = previous_install(&path, Platform::for_worktree(worktree))
+        {
+            version = HarperBinary::managed(previous.clone()).version;
+            path = previous;
+        }
+
+        let version = version.or_else(|| options.version.clone());
+        self.worktrees
+            .entry(worktree.id())
+            .or_default()
+            .server_version = version.clone();
+        let worktree_root = match (source, wsl::distro(worktree)) {
+            (BinarySource::Wsl, Some(distro)) => wsl::to_linux_path(&worktree.root_path(), &distro),
+            _ => worktree.root_path(),
+        };
+        let placeholders = [
+            ("${worktree_root}", Some(worktree_root)),
+            ("${version}", version),
+            ("${config_dir}", config_dir(platform, &worktree.shell_env())),
+        ];
+
+        let mut command = if wrapped || !options.wrapper.is_empty() {
+            absolute_path(&path)?
+        } else {
+            path_str(&path)?.to_string()
+        };
+        let mut args = args;
+        if let Some((program, wrapper_args)) = options.wrapper.split_first() {
+            args = wrapper_args
+                .iter()
+                .cloned()
+                .chain(std::iter::once(command))
+                .chain(args)
+                .collect();
+            command = program.clone();
+        }
+
+        let mut args = args
+            .into_iter()
+            .map(|arg| {
+                placeholders
+                    .iter()
+                    .fold(arg, |arg, (placeholder, value)| match value {
+                        Some(value) => arg.replace(placeholder, value),
+                        None => arg,
+                    })
+            })
+            .collect();
+
+        let mut env = match options.environment {
+            Some(allowed) => allowed_env(worktree.shell_env(), &allowed),
+            None => env.unwrap_or_default(),
+        };
+        if let Some(level) = options.log_level.as_deref() {
+            apply_log_level(&mut env, level);
+        }
 
-        let command = path
-            .to_str()
-            .ok_or("Failed to convert binary path to string")?
-            .to_string();
-        let args = args.unwrap_or_else(|| vec!["--stdio".to_string()]);
-        let env = env.unwrap_or_default();
+        if wrapped && let Ok(dir) = std::env::current_dir() {
+            let status = dir.join(server_log::exit_file(worktree.id()));
+            let log = capture_stderr.then(|| {
+                server_log::rotate();
+                dir.join(server_log::STDERR_LOG)
+            });
+            let log = log.as_deref().map(path_str).transpose()?;
+            (command, args) = server_log::wrap(command, args, path_str(&status)?, log, backoff);
+        }
 
         Ok(Command { command, args, env })
     }
@@ -182,6 +949,9 @@ impl zed::Extension for HarperExtension {
         language_server_id: &zed_extension_api::LanguageServerId,
         worktree: &zed_extension_api::Worktree,
     ) -> Result<Option<zed_extension_api::serde_json::Value>> {
+        if self.in_safe_mode(worktree) {
+            return Ok(None);
+        }
         let options = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
             .ok()
             .and_then(|lsp_settings| lsp_settings.initialization_options.clone());
@@ -191,20 +961,413 @@ impl zed::Extension for HarperExtension {
 
     fn language_server_workspace_configuration(
         &mut self,
-        language_server_id: &zed_extension_api::LanguageServerId,
+        _language_server_id: &zed_extension_api::LanguageServerId,
         worktree: &zed_extension_api::Worktree,
     ) -> Result<Option<zed_extension_api::serde_json::Value>> {
-        let settings = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
-            .ok()
-            .and_then(|lsp_settings| {
-                lsp_settings
-                    .settings
-                    .clone()
-                    .or_else(|| Some(json!({ "harper-ls": { } })))
+        if self.is_paused(worktree) {
+            return Ok(Some(config::paused_configuration()));
+        }
+        if self.in_safe_mode(worktree) {
+            return Ok(Some(config::safe_mode_configuration()));
+        }
+        let mut warnings = Vec::new();
+        let server_version = self
+            .worktrees
+            .get(&worktree.id())
+            .and_then(|state| state.server_version.clone());
+        let settings =
+            config::effective_configuration(worktree, server_version.as_deref(), &mut warnings);
+        for warning in warnings {
+            self.warn(worktree.id(), warning);
+        }
+
+        Ok(Some(settings?))
+    }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>> {
+        match command.name.as_str() {
+            SLASH_COMMAND => Ok(commands::complete(&args)),
+            command => Err(format!("Unknown slash command: {command}")),
+        }
+    }
+
+    fn run_slash_command(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput> {
+        match command.name.as_str() {
+            SLASH_COMMAND => commands::run(self, &args, worktree),
+            command => Err(format!("Unknown slash command: {command}")),
+        }
+    }
+}
+
+/// Returns the pre-staged binary in `binary_share_dir`, laid out as
+/// `<dir>/<version>/<target triple>/harper-ls`, with `latest` standing in for the version
+/// when none is pinned.
+///
+/// The extension cannot read or write outside its own directory, so the share is never
+/// populated and the binary is launched in place. Whether it exists is asked of the
+/// worktree's host; when it does not, the next source, normally the managed install, is used.
+fn shared_binary(worktree: &zed::Worktree, options: &ExtensionOptions) -> Option<HarperBinary> {
+    let share_dir = options.binary_share_dir.as_ref()?;
+    let platform = Platform::for_worktree(worktree);
+    let target = platform.asset_target();
+
+    let path = Path::new(share_dir)
+        .join(
+            options
+                .version
+                .as_deref()
+                .map_or("latest".to_string(), release_tag),
+        )
+        .join(target.triple())
+        .join(platform.binary_file_name());
+    let path = worktree.which(path.to_str()?)?;
+
+    Some(HarperBinary {
+        path: PathBuf::from(path),
+        args: server_args(None),
+        env: None,
+        source: BinarySource::Share,
+        version: options.version.clone(),
+    })
+}
+
+/// Parses the `resolution` option, defaulting to every source in the default order.
+/// Unknown names are skipped with a warning.
+fn resolution_order(names: &[String], warnings: &mut Vec<String>) -> Vec<BinarySource> {
+    if names.is_empty() {
+        return BinarySource::ALL.to_vec();
+    }
+
+    let mut order = Vec::new();
+    for name in names {
+        match BinarySource::ALL
+            .into_iter()
+            .find(|source| source.name().eq_ignore_ascii_case(name.trim()))
+        {
+            Some(source) if !order.contains(&source) => order.push(source),
+            Some(_) => {}
+            None => warnings.push(format!(
+                "Unknown binary source `{name}` in `resolution`; expected one of {}",
+                BinarySource::ALL.map(BinarySource::name).join(", ")
+            )),
+        }
+    }
+    order
+}
+
+/// Checks a `binary.path` from the settings, resolving bare command names on the
+/// worktree's PATH. Absolute paths outside the extension's sandbox cannot be inspected
+/// and are passed on as-is.
+fn validate_binary_path(worktree: &zed::Worktree, path: &str) -> Result<PathBuf, ResolveError> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(ResolveError::BinaryNotFound {
+            path: path.to_string(),
+        });
+    }
+
+    if !path.contains(['/', '\\']) {
+        let os = Platform::for_worktree(worktree).os;
+        return shims::which(worktree, path, os)
+            .map(PathBuf::from)
+            .ok_or_else(|| ResolveError::BinaryNotFound {
+                path: path.to_string(),
             });
+    }
+
+    let path = PathBuf::from(path);
+    match fs::metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => Err(ResolveError::BinaryIsDirectory {
+            path: path.display().to_string(),
+        }),
+        _ => Ok(path),
+    }
+}
+
+/// Returns a path as the string Zed's API expects. Paths may contain spaces and any
+/// Unicode characters; only byte sequences that are not UTF-8 cannot be passed on.
+fn path_str(path: &Path) -> Result<&str, ResolveError> {
+    path.to_str().ok_or_else(|| ResolveError::InvalidPath {
+        path: path.display().to_string(),
+    })
+}
+
+/// Makes a managed path, which is relative to the extension directory, absolute for
+/// programs that do not run there, such as a `wrapper`.
+fn absolute_path(path: &Path) -> Result<String, ResolveError> {
+    match std::env::current_dir() {
+        Ok(dir) if path.is_relative() => path_str(&dir.join(path)).map(str::to_string),
+        _ => path_str(path).map(str::to_string),
+    }
+}
+
+/// Keeps the variables named in the `environment` option; a trailing `*` matches a prefix,
+/// as in `LC_*`.
+fn allowed_env(env: Vec<(String, String)>, allowed: &[String]) -> Vec<(String, String)> {
+    env.into_iter()
+        .filter(|(key, _)| {
+            allowed.iter().any(|name| match name.strip_suffix('*') {
+                Some(prefix) => key.starts_with(prefix),
+                None => key == name,
+            })
+        })
+        .collect()
+}
+
+/// Sets `RUST_LOG` for a known `log_level`, and `RUST_BACKTRACE` at `debug` and `trace`
+/// unless the environment already sets it, so panics come with a backtrace.
+fn apply_log_level(env: &mut Vec<(String, String)>, level: &str) {
+    if !LOG_LEVELS.contains(&level) {
+        return;
+    }
+    env.retain(|(key, _)| key != "RUST_LOG");
+    env.push(("RUST_LOG".to_string(), level.to_string()));
+    if matches!(level, "debug" | "trace") && !env.iter().any(|(key, _)| key == "RUST_BACKTRACE") {
+        env.push(("RUST_BACKTRACE".to_string(), "1".to_string()));
+    }
+}
+
+fn push_capped(messages: &mut Vec<String>, message: String) {
+    if messages.len() >= MAX_MESSAGES {
+        messages.remove(0);
+    }
+    messages.push(message);
+}
+
+/// Appends user arguments to the default `--stdio` unless they select a transport themselves.
+fn server_args(user_args: Option<Vec<String>>) -> Vec<String> {
+    let user_args = user_args.unwrap_or_default();
+    let overrides_transport = user_args.iter().any(|arg| {
+        TRANSPORT_FLAGS
+            .iter()
+            .any(|flag| arg == flag || arg.starts_with(&format!("{flag}=")))
+    });
+
+    if overrides_transport {
+        user_args
+    } else {
+        std::iter::once(STDIO_FLAG.to_string())
+            .chain(user_args)
+            .collect()
+    }
+}
+
+/// Looks up a variable in a shell environment, treating an empty value as unset.
+fn env_var<'a>(env: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    env.iter()
+        .find(|(key, value)| key == name && !value.is_empty())
+        .map(|(_, value)| value.as_str())
+}
+
+/// Returns the user's configuration directory, as Harper itself resolves it.
+fn config_dir(platform: Platform, env: &[(String, String)]) -> Option<String> {
+    let var = |name| env_var(env, name).map(str::to_string);
+
+    match platform.os {
+        zed::Os::Linux => {
+            var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| format!("{home}/.config")))
+        }
+        zed::Os::Mac => var("HOME").map(|home| format!("{home}/Library/Application Support")),
+        zed::Os::Windows => var("APPDATA"),
+    }
+}
 
-        Ok(settings)
+/// Returns the user's local data directory, as Harper itself resolves it.
+fn data_dir(platform: Platform, env: &[(String, String)]) -> Option<String> {
+    let var = |name| env_var(env, name).map(str::to_string);
+
+    match platform.os {
+        zed::Os::Linux => {
+            var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| format!("{home}/.local/share")))
+        }
+        zed::Os::Mac => var("HOME").map(|home| format!("{home}/Library/Application Support")),
+        zed::Os::Windows => var("LOCALAPPDATA"),
     }
 }
 
+/// Returns the version of a `harper-ls-<version>-<target>` directory name. Prerelease
+/// versions contain dashes too, so the target is stripped from the end.
+fn dir_version(name: &str) -> Option<&str> {
+    let rest = name.strip_prefix(&format!("{NAME}-"))?;
+    Some(
+        platform::triples()
+            .find_map(|triple| rest.strip_suffix(triple.as_str())?.strip_suffix('-'))
+            .unwrap_or(rest),
+    )
+}
+
+fn is_version_dir(dir: &Path, version: &str) -> bool {
+    dir.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(dir_version)
+        .is_some_and(|dir_version| {
+            dir_version.trim_start_matches('v') == version.trim_start_matches('v')
+        })
+}
+
+/// Returns the version directories installed for the platform's target, according to
+/// the install manifest, with their binaries, newest first.
+fn installed_versions(platform: Platform) -> Vec<(String, PathBuf)> {
+    // Builds for an emulated target run too, e.g. x86_64 ones on Windows on ARM.
+    let targets: Vec<String> = std::iter::once(platform)
+        .chain(platform.emulated())
+        .map(|platform| platform.asset_target().triple())
+        .collect();
+
+    let mut versions: Vec<(Vec<u64>, String, PathBuf)> = manifest::read()
+        .into_iter()
+        .filter(|install| targets.contains(&install.target))
+        .filter_map(|install| {
+            let version = parse_version(&install.version)?;
+            let binary = PathBuf::from(&install.dir).join(platform.binary_file_name());
+            binary.exists().then_some((version, install.dir, binary))
+        })
+        .collect();
+
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions
+        .into_iter()
+        .map(|(_, name, binary)| (name, binary))
+        .collect()
+}
+
+/// Returns the directory of the newest install of each target other than `current`.
+fn newest_per_target(current: &str) -> HashSet<String> {
+    let mut newest: HashMap<String, (Vec<u64>, String)> = HashMap::new();
+    for install in manifest::read() {
+        let Some(version) = parse_version(&install.version) else {
+            continue;
+        };
+        if install.target != current
+            && newest
+                .get(&install.target)
+                .is_none_or(|(known, _)| *known < version)
+        {
+            newest.insert(install.target, (version, install.dir));
+        }
+    }
+    newest.into_values().map(|(_, dir)| dir).collect()
+}
+
+fn previous_install(current: &Path, platform: Platform) -> Option<PathBuf> {
+    installed_versions(platform)
+        .into_iter()
+        .map(|(_, binary)| binary)
+        .find(|binary| binary != current)
+}
+
+/// Harper tags its releases `v<version>`.
+fn release_tag(version: &str) -> String {
+    format!("v{}", version.trim_start_matches('v'))
+}
+
+/// Reads the version from a path such as `~/.cargo/harper-ls-0.36.0/bin/harper-ls` or
+/// `/nix/store/<hash>-harper-ls-0.36.0/bin/harper-ls`, for binaries that report none.
+fn path_version(path: &Path) -> Option<Vec<u64>> {
+    path.components().find_map(|component| {
+        component
+            .as_os_str()
+            .to_str()?
+            .split('-')
+            .find_map(|part| parse_version(part).filter(|version| version.len() >= 2))
+    })
+}
+
+/// Parses the numeric core of a version; prereleases and build metadata such as
+/// `0.36.0-rc.1` order with their release.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim_start_matches('v');
+    let core = version.split(['-', '+']).next().unwrap_or(version);
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
 zed::register_extension!(HarperExtension);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_version_reads_numeric_versions() {
+        assert_eq!(parse_version("v0.36.0"), Some(vec![0, 36, 0]));
+        assert_eq!(parse_version("1.2"), Some(vec![1, 2]));
+        assert_eq!(parse_version("0.36.0-rc.1"), Some(vec![0, 36, 0]));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn dir_version_strips_the_prefix_and_target() {
+        assert_eq!(
+            dir_version("harper-ls-0.36.0-x86_64-unknown-linux-gnu"),
+            Some("0.36.0")
+        );
+        assert_eq!(
+            dir_version("harper-ls-0.37.0-rc.1-aarch64-apple-darwin"),
+            Some("0.37.0-rc.1")
+        );
+        assert_eq!(dir_version("harper-cli-0.36.0"), None);
+    }
+
+    #[test]
+    fn is_version_dir_ignores_the_tag_prefix() {
+        let dir = Path::new("harper-ls-0.36.0-x86_64-unknown-linux-gnu");
+        assert!(is_version_dir(dir, "v0.36.0"));
+        assert!(!is_version_dir(dir, "0.35.0"));
+
+        let prerelease = Path::new("harper-ls-0.37.0-rc.1-x86_64-unknown-linux-gnu");
+        assert!(is_version_dir(prerelease, "v0.37.0-rc.1"));
+        assert!(!is_version_dir(prerelease, "0.37.0"));
+        assert!(!is_version_dir(Path::new("harper-ls-nightly"), "latest"));
+    }
+
+    #[test]
+    fn path_version_reads_versioned_directories() {
+        assert_eq!(
+            path_version(Path::new(
+                "/nix/store/abc123-harper-ls-0.36.0/bin/harper-ls"
+            )),
+            Some(vec![0, 36, 0])
+        );
+        assert_eq!(
+            path_version(Path::new(
+                "/home/me/.local/share/mise/installs/harper-ls/v0.35.1/bin/harper-ls"
+            )),
+            Some(vec![0, 35, 1])
+        );
+        assert_eq!(path_version(Path::new("/usr/local/bin/harper-ls")), None);
+    }
+
+    #[test]
+    fn server_args_default_to_stdio() {
+        assert_eq!(server_args(None), args(&["--stdio"]));
+        assert_eq!(
+            server_args(Some(args(&["--verbose"]))),
+            args(&["--stdio", "--verbose"])
+        );
+    }
+
+    #[test]
+    fn server_args_keep_a_chosen_transport() {
+        assert_eq!(
+            server_args(Some(args(&["--port=4000"]))),
+            args(&["--port=4000"])
+        );
+        assert_eq!(
+            server_args(Some(args(&["--socket", "9000"]))),
+            args(&["--socket", "9000"])
+        );
+    }
+}
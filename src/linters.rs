@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+/// Long-standing Harper linters, for readable aliases, schema completion and `mode` when
+/// the rules of the server's version are unknown. harper-ls adds rules with most
+/// releases, so this is not the full list; installs record their own (see [`defaults`]).
+pub const KNOWN: &[&str] = &[
+    "AnA",
+    "AvoidCurses",
+    "BoringWords",
+    "CapitalizePersonalPronouns",
+    "CorrectNumberSuffix",
+    "Dashes",
+    "DotInitialisms",
+    "EllipsisLength",
+    "LinkingVerbs",
+    "LongSentences",
+    "MultipleSequentialPronouns",
+    "NumberSuffixCapitalization",
+    "RepeatedWords",
+    "SentenceCapitalization",
+    "Spaces",
+    "SpellCheck",
+    "SpelledNumbers",
+    "TerminatingConjunctions",
+    "ThatWhich",
+    "UnclosedQuotes",
+    "UseGenitive",
+    "WrongQuotes",
+];
+
+/// Folds case, spaces, dashes and underscores, so `"repeated words"` matches `RepeatedWords`.
+fn fold(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns the canonical name a readable alias refers to.
+pub fn canonical(name: &str) -> Option<&'static str> {
+    let folded = fold(name);
+    KNOWN.iter().copied().find(|known| fold(known) == folded)
+}
+
+/// Suggests the rule closest to an unrecognized name.
+fn suggestion<'a>(name: &str, rules: &'a [String]) -> Option<&'a str> {
+    let folded = fold(name);
+    rules
+        .iter()
+        .map(|rule| (edit_distance(&folded, &fold(rule)), rule.as_str()))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, rule)| rule)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Rewrites aliases in the `linters` map to canonical names. See [`resolve`] for `rules`.
+pub fn normalize(
+    linters: &mut BTreeMap<String, bool>,
+    rules: &[String],
+    warnings: &mut Vec<String>,
+) {
+    *linters = std::mem::take(linters)
+        .into_iter()
+        .map(|(name, enabled)| (resolve(&name, rules, warnings), enabled))
+        .collect();
+}
+
+/// Returns the canonical name for `name`, or `name` itself. `rules` are the linters of
+/// the server's version, recorded from its `lint_group.rs` when it was installed; names
+/// matching none of them are warned about. When they are unknown (empty), any name is
+/// forwarded without a warning, since a hardcoded list would miss newer rules.
+pub fn resolve(name: &str, rules: &[String], warnings: &mut Vec<String>) -> String {
+    let folded = fold(name);
+    if let Some(rule) = rules.iter().find(|rule| fold(rule) == folded) {
+        return rule.clone();
+    }
+    let name = canonical(name).unwrap_or(name);
+
+    if !rules.is_empty() {
+        let hint = suggestion(name, rules)
+            .map(|rule| format!("; did you mean {rule}?"))
+            .unwrap_or_default();
+        warnings.push(format!(
+            "Unknown linter {name:?} for this harper-ls version{hint}"
+        ));
+    }
+    name.to_string()
+}
+
+/// File of the Harper source tree that registers the linters and their defaults.
+pub const LINT_GROUP_SOURCE: &str = "harper-core/src/linting/lint_group.rs";
+
+/// Reads the default state of each linter from [`LINT_GROUP_SOURCE`], where rules are
+/// registered as `insert_struct_rule!(SpellCheck, true);` and similar macro calls.
+pub fn defaults(source: &str) -> BTreeMap<String, bool> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (macro_name, rest) = line.split_once("!(")?;
+            if !macro_name.starts_with("insert_") || !macro_name.ends_with("_rule") {
+                return None;
+            }
+            let (name, enabled) = rest.trim_end_matches([')', ';']).split_once(',')?;
+            let enabled = enabled.trim().parse().ok()?;
+            Some((name.trim().to_string(), enabled))
+        })
+        .collect()
+}
+
+/// Returns the linters on by default in `new` that were off or absent in `old`.
+pub fn newly_enabled(old: &BTreeMap<String, bool>, new: &BTreeMap<String, bool>) -> Vec<String> {
+    new.iter()
+        .filter(|(name, enabled)| **enabled && old.get(*name) != Some(&true))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linters(names: &[&str]) -> BTreeMap<String, bool> {
+        names.iter().map(|name| (name.to_string(), false)).collect()
+    }
+
+    #[test]
+    fn normalize_resolves_aliases() {
+        let mut map = linters(&["repeated words", "spell_check"]);
+        let mut warnings = Vec::new();
+        normalize(&mut map, &[], &mut warnings);
+        assert_eq!(map, linters(&["RepeatedWords", "SpellCheck"]));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn normalize_forwards_unknown_names_without_rules() {
+        let mut map = linters(&["BrandNewRule"]);
+        let mut warnings = Vec::new();
+        normalize(&mut map, &[], &mut warnings);
+        assert_eq!(map, linters(&["BrandNewRule"]));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn normalize_checks_names_against_rules() {
+        let rules = vec!["BrandNewRule".to_string(), "SpellCheck".to_string()];
+        let mut map = linters(&["brand new rule", "SpelCheck"]);
+        let mut warnings = Vec::new();
+        normalize(&mut map, &rules, &mut warnings);
+        assert_eq!(map, linters(&["BrandNewRule", "SpelCheck"]));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("did you mean SpellCheck?"));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::NAME;
+use crate::install::STAGING_SUFFIX;
+use crate::platform;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use zed_extension_api::serde_json;
+
+/// Index of the managed installs in the extension directory.
+pub const MANIFEST_FILE: &str = "installs.json";
+
+/// A version directory and the target its binary was built for.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Install {
+    pub dir: String,
+    pub version: String,
+    pub target: String,
+    pub asset: String,
+    /// The URL the asset was downloaded from.
+    #[serde(default)]
+    pub source: String,
+    /// The linters of the version, read from its `lint_group.rs`; empty when unknown.
+    #[serde(default)]
+    pub rules: Vec<String>,
+}
+
+/// Returns the recorded installs whose directories still exist.
+pub fn read() -> Vec<Install> {
+    migrate();
+    fs::read_to_string(MANIFEST_FILE)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Vec<Install>>(&text).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|install| Path::new(&install.dir).is_dir())
+        .collect()
+}
+
+/// Records a finished install, replacing an earlier entry for the same directory.
+pub fn record(install: Install) {
+    let mut installs = read();
+    installs.retain(|known| known.dir != install.dir);
+    installs.push(install);
+    write(&installs);
+}
+
+/// Returns the linters recorded for an installed version; empty when unknown.
+pub fn rules(version: &str) -> Vec<String> {
+    read()
+        .into_iter()
+        .find(|install| {
+            install.version.trim_start_matches('v') == version.trim_start_matches('v')
+                && !install.rules.is_empty()
+        })
+        .map(|install| install.rules)
+        .unwrap_or_default()
+}
+
+/// Drops the entries of directories that were removed.
+pub fn prune() {
+    write(&read());
+}
+
+/// Records the version directories of installs from before the manifest existed, which
+/// would otherwise be unknown, never reused and cleaned up. Does nothing once it exists.
+fn migrate() {
+    if Path::new(MANIFEST_FILE).exists() {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(".") else {
+        return;
+    };
+
+    let installs: Vec<Install> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| existing_install(&entry.file_name().into_string().ok()?))
+        .collect();
+    write(&installs);
+}
+
+/// Describes an install from its `harper-ls-<version>-<target>` directory, or for the
+/// older `harper-ls-<version>` directories, from the binary inside it.
+fn existing_install(dir: &str) -> Option<Install> {
+    let rest = dir.strip_prefix(&format!("{NAME}-"))?;
+    if dir.ends_with(STAGING_SUFFIX) {
+        return None;
+    }
+
+    let named = platform::triples().find_map(|triple| {
+        let version = rest.strip_suffix(triple.as_str())?.strip_suffix('-')?;
+        Some((version.to_string(), triple))
+    });
+    let (version, target) = match named {
+        Some(named) => named,
+        None => {
+            let platform = platform::all().find(|platform| {
+                let binary = Path::new(dir).join(platform.binary_file_name());
+                platform.asset_target().check_binary(&binary).is_ok()
+            })?;
+            (rest.to_string(), platform.asset_target().triple())
+        }
+    };
+
+    Some(Install {
+        dir: dir.to_string(),
+        version,
+        target,
+        asset: String::new(),
+        source: String::new(),
+        rules: Vec::new(),
+    })
+}
+
+fn write(installs: &[Install]) {
+    let written = serde_json::to_string_pretty(installs)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(MANIFEST_FILE, json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("harper-ls: failed to update {MANIFEST_FILE}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_install_reads_targeted_directories() {
+        let install = existing_install("harper-ls-0.37.0-rc.1-aarch64-apple-darwin").unwrap();
+        assert_eq!(install.version, "0.37.0-rc.1");
+        assert_eq!(install.target, "aarch64-apple-darwin");
+        assert!(install.rules.is_empty());
+    }
+
+    #[test]
+    fn existing_install_skips_other_directories() {
+        let staged = format!("harper-ls-0.36.0-x86_64-unknown-linux-gnu{STAGING_SUFFIX}");
+        assert!(existing_install(&staged).is_none());
+        assert!(existing_install("harper-cli-0.36.0-x86_64-unknown-linux-gnu").is_none());
+        // An untargeted directory without a binary to identify.
+        assert!(existing_install("harper-ls-0.30.0").is_none());
+    }
+}
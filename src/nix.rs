@@ -0,0 +1,46 @@
+use crate::{BinarySource, HarperBinary, NAME, server_args};
+use std::path::PathBuf;
+use zed_extension_api as zed;
+
+/// Returns whether the worktree host is NixOS, where the prebuilt glibc binary cannot run.
+pub fn is_nixos(env: &[(String, String)]) -> bool {
+    env.iter().any(|(key, value)| {
+        key == "__NIXOS_SET_ENVIRONMENT_DONE"
+            || (key == "PATH"
+                && value
+                    .split(':')
+                    .any(|dir| dir == "/run/current-system/sw/bin"))
+    })
+}
+
+/// Returns a harper-ls provided through Nix when none is on `PATH`: the worktree's flake
+/// devshell if it mentions Harper, otherwise `pkgs.harper` on NixOS.
+pub fn binary(worktree: &zed::Worktree) -> Option<HarperBinary> {
+    let nix = worktree.which("nix")?;
+    let env = worktree.shell_env();
+
+    let prefix = if worktree
+        .read_text_file("flake.nix")
+        .is_ok_and(|flake| flake.contains("harper"))
+    {
+        vec!["develop".to_string(), worktree.root_path()]
+    } else if is_nixos(&env) {
+        vec!["shell".to_string(), "nixpkgs#harper".to_string()]
+    } else {
+        return None;
+    };
+
+    let args = prefix
+        .into_iter()
+        .chain(["--command".to_string(), NAME.to_string()])
+        .chain(server_args(None))
+        .collect();
+
+    Some(HarperBinary {
+        path: PathBuf::from(nix),
+        args,
+        env: Some(env),
+        source: BinarySource::Nix,
+        version: None,
+    })
+}
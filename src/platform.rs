@@ -0,0 +1,539 @@
+use crate::{NAME, env_var};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use zed_extension_api as zed;
+
+/// Archive formats `zed::download_file` can extract, by file name suffix.
+const ARCHIVE_TYPES: &[(&str, zed::DownloadedFileType)] = &[
+    (".tar.gz", zed::DownloadedFileType::GzipTar),
+    (".tgz", zed::DownloadedFileType::GzipTar),
+    (".zip", zed::DownloadedFileType::Zip),
+    (".gz", zed::DownloadedFileType::Gzip),
+];
+
+/// Homebrew's prefix on Apple Silicon; Intel Macs use `/usr/local`.
+const APPLE_SILICON_HOMEBREW: &str = "/opt/homebrew";
+
+/// The OS and architecture a harper-ls binary has to run on.
+///
+/// Resolved per worktree rather than from the extension's own platform, so that
+/// installs follow the host the worktree lives on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub os: zed::Os,
+    pub arch: zed::Architecture,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        let (os, arch) = zed::current_platform();
+        Self { os, arch }
+    }
+
+    /// Resolves the platform of the host the worktree lives on from its shell environment,
+    /// falling back to the platform the extension runs on.
+    ///
+    /// For remote projects these normally agree, as Zed runs extensions on the remote host.
+    pub fn for_worktree(worktree: &zed::Worktree) -> Self {
+        Self::from_env(&worktree.shell_env())
+    }
+
+    fn from_env(env: &[(String, String)]) -> Self {
+        Self::from_env_or(env, Self::current)
+    }
+
+    /// Like [`Self::from_env`], with `current` giving the platform the environment does
+    /// not reveal.
+    fn from_env_or(env: &[(String, String)], current: impl Fn() -> Self) -> Self {
+        let var = |name| env_var(env, name);
+
+        let os = if var("OS") == Some("Windows_NT") {
+            zed::Os::Windows
+        } else if let Some(home) = var("HOME") {
+            if home.starts_with("/Users/") {
+                zed::Os::Mac
+            } else if home.starts_with("/home/") || home == "/root" {
+                zed::Os::Linux
+            } else {
+                current().os
+            }
+        } else {
+            current().os
+        };
+
+        let arch = match var("PROCESSOR_ARCHITECTURE").or_else(|| var("HOSTTYPE")) {
+            Some("AMD64" | "x86_64") => zed::Architecture::X8664,
+            Some("ARM64" | "aarch64" | "arm64") => zed::Architecture::Aarch64,
+            Some("x86" | "i386" | "i686") => zed::Architecture::X86,
+            _ => current().arch,
+        };
+
+        // A 32-bit shell on 64-bit Windows reports x86; the native architecture is in
+        // PROCESSOR_ARCHITEW6432, and x86_64 builds run there.
+        let arch = match (os, arch, var("PROCESSOR_ARCHITEW6432")) {
+            (zed::Os::Windows, zed::Architecture::X86, Some("AMD64")) => zed::Architecture::X8664,
+            (zed::Os::Windows, zed::Architecture::X86, Some("ARM64")) => zed::Architecture::Aarch64,
+            _ => arch,
+        };
+
+        // Windows on ARM runs x64 programs emulated and reports AMD64 to them, but the
+        // processor identifier names the real CPU.
+        let arch = if os == zed::Os::Windows
+            && arch == zed::Architecture::X8664
+            && (var("PROCESSOR_ARCHITEW6432") == Some("ARM64")
+                || var("PROCESSOR_IDENTIFIER").is_some_and(|cpu| cpu.starts_with("ARM")))
+        {
+            zed::Architecture::Aarch64
+        } else {
+            arch
+        };
+
+        // Under Rosetta, Zed and the shell report x86_64 on Apple Silicon. A native
+        // Homebrew prefix gives the real architecture away, and the native build avoids
+        // running harper-ls emulated.
+        let arch = if os == zed::Os::Mac
+            && arch == zed::Architecture::X8664
+            && (var("HOMEBREW_PREFIX") == Some(APPLE_SILICON_HOMEBREW)
+                || var("PATH").is_some_and(|path| {
+                    path.split(':')
+                        .any(|dir| dir == format!("{APPLE_SILICON_HOMEBREW}/bin"))
+                })) {
+            zed::Architecture::Aarch64
+        } else {
+            arch
+        };
+
+        Self { os, arch }
+    }
+
+    /// The platform whose binaries this one runs through emulation: x86_64 builds run
+    /// on ARM Macs under Rosetta and on Windows on ARM.
+    pub fn emulated(self) -> Option<Self> {
+        (self.arch == zed::Architecture::Aarch64 && self.os != zed::Os::Linux).then_some(Self {
+            arch: zed::Architecture::X8664,
+            ..self
+        })
+    }
+
+    /// Picks the release asset of `program` for this platform: the native build, or
+    /// else one that runs [`Self::emulated`].
+    pub fn select_asset<'a>(
+        self,
+        program: &str,
+        assets: &'a [zed::GithubReleaseAsset],
+    ) -> Option<(AssetTarget, &'a zed::GithubReleaseAsset)> {
+        std::iter::once(self)
+            .chain(self.emulated())
+            .map(Platform::asset_target)
+            .find_map(|target| {
+                let asset = target.find_asset(program, assets)?;
+                Some((target, asset))
+            })
+    }
+
+    pub fn binary_file_name(self) -> &'static str {
+        match self.os {
+            zed::Os::Windows => "harper-ls.exe",
+            _ => NAME,
+        }
+    }
+
+    pub fn executable_name(self, program: &str) -> String {
+        match self.os {
+            zed::Os::Windows => format!("{program}.exe"),
+            _ => program.to_string(),
+        }
+    }
+
+    pub fn asset_target(self) -> AssetTarget {
+        let arch = match self.arch {
+            zed::Architecture::Aarch64 => "aarch64",
+            zed::Architecture::X8664 => "x86_64",
+            zed::Architecture::X86 => "i686",
+        };
+
+        let (os, file_ext) = match self.os {
+            zed::Os::Mac => ("apple-darwin", "tar.gz"),
+            zed::Os::Linux => ("unknown-linux-gnu", "tar.gz"),
+            zed::Os::Windows => ("pc-windows-msvc", "zip"),
+        };
+
+        AssetTarget { arch, os, file_ext }
+    }
+}
+
+/// Returns every supported platform.
+pub fn all() -> impl Iterator<Item = Platform> {
+    [zed::Os::Mac, zed::Os::Linux, zed::Os::Windows]
+        .into_iter()
+        .flat_map(|os| {
+            [
+                zed::Architecture::Aarch64,
+                zed::Architecture::X8664,
+                zed::Architecture::X86,
+            ]
+            .map(|arch| Platform { os, arch })
+        })
+}
+
+/// Returns the target triples of every supported platform.
+pub fn triples() -> impl Iterator<Item = String> {
+    all().map(|platform| platform.asset_target().triple())
+}
+
+pub struct AssetTarget {
+    arch: &'static str,
+    os: &'static str,
+    file_ext: &'static str,
+}
+
+impl AssetTarget {
+    pub fn triple(&self) -> String {
+        format!("{}-{}", self.arch, self.os)
+    }
+
+    pub fn asset_name(&self) -> String {
+        self.asset_name_for(NAME)
+    }
+
+    /// Reads the executable header of `binary` and checks that it was built for this
+    /// target. Returns a description of what was found otherwise.
+    pub fn check_binary(&self, binary: &Path) -> Result<(), String> {
+        let mut header = [0; 4096];
+        let len = File::open(binary)
+            .and_then(|mut file| file.read(&mut header))
+            .map_err(|e| format!("unreadable ({e})"))?;
+        let header = &header[..len];
+
+        let u16_at = |at: usize| Some(u16::from_le_bytes(header.get(at..at + 2)?.try_into().ok()?));
+        let u32_at = |at: usize| Some(u32::from_le_bytes(header.get(at..at + 4)?.try_into().ok()?));
+
+        let (os, arch) = match header {
+            [0x7f, b'E', b'L', b'F', ..] => (
+                "unknown-linux-gnu",
+                match u16_at(18) {
+                    Some(0x03) => "i686",
+                    Some(0x3e) => "x86_64",
+                    Some(0xb7) => "aarch64",
+                    _ => "another architecture",
+                },
+            ),
+            // Universal binaries contain every architecture.
+            [0xca, 0xfe, 0xba, 0xbe, ..] => ("apple-darwin", self.arch),
+            [0xcf, 0xfa, 0xed, 0xfe, ..] => (
+                "apple-darwin",
+                match u32_at(4) {
+                    Some(0x0100_0007) => "x86_64",
+                    Some(0x0100_000c) => "aarch64",
+                    _ => "another architecture",
+                },
+            ),
+            [b'M', b'Z', ..] => (
+                "pc-windows-msvc",
+                match u32_at(0x3c).and_then(|pe| u16_at(pe as usize + 4)) {
+                    Some(0x014c) => "i686",
+                    Some(0x8664) => "x86_64",
+                    Some(0xaa64) => "aarch64",
+                    _ => "another architecture",
+                },
+            ),
+            _ => return Err("not an executable".to_string()),
+        };
+
+        if os == self.os && arch == self.arch {
+            Ok(())
+        } else {
+            Err(format!("a {arch}-{os} executable"))
+        }
+    }
+
+    /// Returns the directory a program's release is installed into, qualified by the
+    /// target so that binaries for another architecture are never picked up.
+    pub fn version_dir(&self, program: &str, version: &str) -> String {
+        format!("{program}-{version}-{}", self.triple())
+    }
+
+    /// Returns the asset name of another program published with the same release.
+    pub fn asset_name_for(&self, program: &str) -> String {
+        format!("{program}-{}.{}", self.triple(), self.file_ext)
+    }
+
+    /// Finds the release asset of `program` for this target, preferring the usual name.
+    ///
+    /// Otherwise the asset is matched fuzzily, so packaging and naming changes upstream keep
+    /// working: it has to mention the program, the architecture and the OS (under any common
+    /// spelling), and extractable archives, glibc builds and shorter names win.
+    pub fn find_asset<'a>(
+        &self,
+        program: &str,
+        assets: &'a [zed::GithubReleaseAsset],
+    ) -> Option<&'a zed::GithubReleaseAsset> {
+        let expected = self.asset_name_for(program);
+        if let Some(asset) = assets.iter().find(|asset| asset.name == expected) {
+            return Some(asset);
+        }
+
+        let arch_names: &[&str] = match self.arch {
+            "aarch64" => &["aarch64", "arm64"],
+            "i686" => &["i686", "i586", "i386", "x86_32", "win32"],
+            _ => &["x86_64", "amd64", "x64"],
+        };
+        let os_names: &[&str] = match self.os {
+            "apple-darwin" => &["darwin", "apple", "macos", "osx"],
+            "pc-windows-msvc" => &["windows", "win64", "win32", "msvc"],
+            _ => &["linux"],
+        };
+        let mentions = |name: &str, aliases: &[&str]| aliases.iter().any(|a| name.contains(a));
+
+        assets
+            .iter()
+            .filter(|asset| {
+                let name = asset.name.to_lowercase();
+                name.starts_with(&format!("{program}-"))
+                    && mentions(&name, arch_names)
+                    && mentions(&name, os_names)
+            })
+            .min_by_key(|asset| {
+                (
+                    archive_type(&asset.name).is_none(),
+                    asset.name.contains("musl"),
+                    asset.name.len(),
+                )
+            })
+    }
+}
+
+/// Returns how `zed::download_file` has to treat an asset, or `None` for archive formats
+/// it cannot extract (such as `.tar.xz` or `.zst`).
+pub fn archive_type(asset_name: &str) -> Option<zed::DownloadedFileType> {
+    if let Some((_, file_type)) = ARCHIVE_TYPES
+        .iter()
+        .find(|(suffix, _)| asset_name.ends_with(suffix))
+    {
+        return Some(*file_type);
+    }
+
+    let extension = asset_name.rsplit_once('.').map(|(_, extension)| extension);
+    match extension {
+        None | Some("exe") => Some(zed::DownloadedFileType::Uncompressed),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets(names: &[&str]) -> Vec<zed::GithubReleaseAsset> {
+        names
+            .iter()
+            .map(|name| zed::GithubReleaseAsset {
+                name: name.to_string(),
+                download_url: format!("https://example.com/{name}"),
+            })
+            .collect()
+    }
+
+    /// The harper-ls assets of a typical release.
+    const RELEASE: &[&str] = &[
+        "harper-ls-aarch64-apple-darwin.tar.gz",
+        "harper-ls-x86_64-apple-darwin.tar.gz",
+        "harper-ls-aarch64-unknown-linux-gnu.tar.gz",
+        "harper-ls-x86_64-unknown-linux-gnu.tar.gz",
+        "harper-ls-x86_64-unknown-linux-musl.tar.gz",
+        "harper-ls-x86_64-pc-windows-msvc.zip",
+        "harper-cli-x86_64-unknown-linux-gnu.tar.gz",
+    ];
+
+    fn platform(os: zed::Os, arch: zed::Architecture) -> Platform {
+        Platform { os, arch }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn select_asset_for_every_platform() {
+        use zed::Architecture::{Aarch64, X86, X8664};
+        use zed::Os::{Linux, Mac, Windows};
+
+        let assets = assets(RELEASE);
+        let cases = [
+            (Mac, Aarch64, Some("harper-ls-aarch64-apple-darwin.tar.gz")),
+            (Mac, X8664, Some("harper-ls-x86_64-apple-darwin.tar.gz")),
+            (
+                Linux,
+                Aarch64,
+                Some("harper-ls-aarch64-unknown-linux-gnu.tar.gz"),
+            ),
+            (
+                Linux,
+                X8664,
+                Some("harper-ls-x86_64-unknown-linux-gnu.tar.gz"),
+            ),
+            (Linux, X86, None),
+            (Windows, X8664, Some("harper-ls-x86_64-pc-windows-msvc.zip")),
+            // No native build, so the x86_64 one runs emulated.
+            (
+                Windows,
+                Aarch64,
+                Some("harper-ls-x86_64-pc-windows-msvc.zip"),
+            ),
+            (Windows, X86, None),
+            (Mac, X86, None),
+        ];
+        for (os, arch, expected) in cases {
+            let selected = platform(os, arch)
+                .select_asset("harper-ls", &assets)
+                .map(|(_, asset)| asset.name.as_str());
+            assert_eq!(selected, expected, "{os:?} {arch:?}");
+        }
+    }
+
+    #[test]
+    fn select_asset_prefers_native_builds_on_arm() {
+        let assets = assets(&[
+            "harper-ls-x86_64-apple-darwin.tar.gz",
+            "harper-ls-aarch64-apple-darwin.tar.gz",
+        ]);
+        let (target, _) = platform(zed::Os::Mac, zed::Architecture::Aarch64)
+            .select_asset("harper-ls", &assets)
+            .unwrap();
+        assert_eq!(target.triple(), "aarch64-apple-darwin");
+
+        let (target, _) = platform(zed::Os::Mac, zed::Architecture::Aarch64)
+            .select_asset("harper-ls", &assets[..1])
+            .unwrap();
+        assert_eq!(target.triple(), "x86_64-apple-darwin");
+    }
+
+    #[test]
+    fn i686_targets_name_32_bit_assets() {
+        let assets = assets(&["harper-ls-i686-unknown-linux-gnu.tar.gz"]);
+        let (target, asset) = platform(zed::Os::Linux, zed::Architecture::X86)
+            .select_asset("harper-ls", &assets)
+            .unwrap();
+        assert_eq!(target.triple(), "i686-unknown-linux-gnu");
+        assert_eq!(asset.name, "harper-ls-i686-unknown-linux-gnu.tar.gz");
+    }
+
+    #[test]
+    fn from_env_detects_the_real_architecture() {
+        use zed::Architecture::{Aarch64, X86, X8664};
+        use zed::Os::{Linux, Mac, Windows};
+
+        let unused = || panic!("the environment names the platform");
+        let cases = [
+            (
+                env(&[("HOME", "/Users/me"), ("HOSTTYPE", "arm64")]),
+                platform(Mac, Aarch64),
+            ),
+            (
+                env(&[("HOME", "/Users/me"), ("HOSTTYPE", "x86_64")]),
+                platform(Mac, X8664),
+            ),
+            // Rosetta reports x86_64, but a native Homebrew gives Apple Silicon away.
+            (
+                env(&[
+                    ("HOME", "/Users/me"),
+                    ("HOSTTYPE", "x86_64"),
+                    ("HOMEBREW_PREFIX", "/opt/homebrew"),
+                ]),
+                platform(Mac, Aarch64),
+            ),
+            (
+                env(&[
+                    ("HOME", "/Users/me"),
+                    ("HOSTTYPE", "x86_64"),
+                    ("PATH", "/usr/bin:/opt/homebrew/bin"),
+                ]),
+                platform(Mac, Aarch64),
+            ),
+            (
+                env(&[("HOME", "/home/me"), ("HOSTTYPE", "aarch64")]),
+                platform(Linux, Aarch64),
+            ),
+            (
+                env(&[("HOME", "/root"), ("HOSTTYPE", "i686")]),
+                platform(Linux, X86),
+            ),
+            (
+                env(&[("OS", "Windows_NT"), ("PROCESSOR_ARCHITECTURE", "AMD64")]),
+                platform(Windows, X8664),
+            ),
+            (
+                env(&[("OS", "Windows_NT"), ("PROCESSOR_ARCHITECTURE", "ARM64")]),
+                platform(Windows, Aarch64),
+            ),
+            // x64 emulation on Windows on ARM reports AMD64.
+            (
+                env(&[
+                    ("OS", "Windows_NT"),
+                    ("PROCESSOR_ARCHITECTURE", "AMD64"),
+                    ("PROCESSOR_IDENTIFIER", "ARMv8 (64-bit) Family 8"),
+                ]),
+                platform(Windows, Aarch64),
+            ),
+            // A 32-bit shell on 64-bit Windows.
+            (
+                env(&[
+                    ("OS", "Windows_NT"),
+                    ("PROCESSOR_ARCHITECTURE", "x86"),
+                    ("PROCESSOR_ARCHITEW6432", "AMD64"),
+                ]),
+                platform(Windows, X8664),
+            ),
+            (
+                env(&[("OS", "Windows_NT"), ("PROCESSOR_ARCHITECTURE", "x86")]),
+                platform(Windows, X86),
+            ),
+        ];
+        for (env, expected) in cases {
+            assert_eq!(Platform::from_env_or(&env, unused), expected, "{env:?}");
+        }
+    }
+
+    fn linux_x86_64() -> AssetTarget {
+        Platform {
+            os: zed::Os::Linux,
+            arch: zed::Architecture::X8664,
+        }
+        .asset_target()
+    }
+
+    #[test]
+    fn find_asset_prefers_the_usual_name() {
+        let assets = assets(&[
+            "harper-ls-x86_64-unknown-linux-musl.tar.gz",
+            "harper-ls-x86_64-unknown-linux-gnu.tar.gz",
+        ]);
+        let asset = linux_x86_64().find_asset("harper-ls", &assets).unwrap();
+        assert_eq!(asset.name, "harper-ls-x86_64-unknown-linux-gnu.tar.gz");
+    }
+
+    #[test]
+    fn find_asset_matches_other_spellings() {
+        let assets = assets(&[
+            "harper-ls-amd64-linux.tar.xz",
+            "harper-ls-amd64-linux-musl.tar.gz",
+            "harper-ls-amd64-linux.tar.gz",
+            "harper-ls-arm64-linux.tar.gz",
+            "harper-cli-amd64-linux.tar.gz",
+        ]);
+        let asset = linux_x86_64().find_asset("harper-ls", &assets).unwrap();
+        assert_eq!(asset.name, "harper-ls-amd64-linux.tar.gz");
+    }
+
+    #[test]
+    fn find_asset_rejects_other_targets() {
+        let assets = assets(&[
+            "harper-ls-aarch64-unknown-linux-gnu.tar.gz",
+            "harper-ls-x86_64-apple-darwin.tar.gz",
+        ]);
+        assert!(linux_x86_64().find_asset("harper-ls", &assets).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::settings::ServerSettings;
+use zed_extension_api as zed;
+
+/// A curated rule configuration selectable with the `preset` extension option.
+pub struct Preset {
+    pub name: &'static str,
+    pub severity: &'static str,
+    pub linters: &'static [(&'static str, bool)],
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "strict",
+        severity: "warning",
+        linters: &[
+            ("LongSentences", true),
+            ("LinkingVerbs", true),
+            ("BoringWords", true),
+            ("SpelledNumbers", true),
+            ("TerminatingConjunctions", true),
+            ("AvoidCurses", true),
+        ],
+    },
+    Preset {
+        name: "relaxed",
+        severity: "hint",
+        linters: &[
+            ("LongSentences", false),
+            ("SentenceCapitalization", false),
+            ("SpelledNumbers", false),
+            ("WrongQuotes", false),
+            ("LinkingVerbs", false),
+            ("BoringWords", false),
+        ],
+    },
+    Preset {
+        name: "technical-docs",
+        severity: "information",
+        linters: &[
+            ("LongSentences", true),
+            ("SpelledNumbers", false),
+            ("SentenceCapitalization", false),
+            ("WrongQuotes", false),
+            ("Dashes", false),
+        ],
+    },
+    Preset {
+        name: "academic",
+        severity: "warning",
+        linters: &[
+            ("LongSentences", true),
+            ("SpelledNumbers", true),
+            ("BoringWords", true),
+            ("AvoidCurses", true),
+            ("WrongQuotes", true),
+        ],
+    },
+];
+
+/// Default for `project_presets`: documentation projects get `technical-docs`.
+pub const PROJECT_PRESETS: &[(&str, &str)] = &[("docs", "technical-docs")];
+
+/// Configuration files of documentation site generators at the worktree root.
+const DOCS_PROJECT_FILES: &[&str] = &[
+    "mkdocs.yml",
+    "mkdocs.yaml",
+    "book.toml",
+    "conf.py",
+    "antora.yml",
+    "antora-playbook.yml",
+    "_config.yml",
+    "hugo.toml",
+    "docusaurus.config.js",
+    "docusaurus.config.ts",
+    ".vitepress/config.js",
+    ".vitepress/config.ts",
+];
+
+/// Build manifests that mark a code project. `package.json` is not among them, because
+/// JavaScript site generators need one too.
+const CODE_PROJECT_FILES: &[&str] = &[
+    "Cargo.toml",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "composer.json",
+    "Gemfile",
+    "mix.exs",
+    "CMakeLists.txt",
+];
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// Guesses whether the worktree is a documentation (`"docs"`) or a code (`"code"`)
+/// project from the files at its root. The extension cannot list the worktree, so the
+/// share of Markdown or reStructuredText files is not counted.
+pub fn project_kind(worktree: &zed::Worktree) -> Option<&'static str> {
+    let exists = |file: &&str| worktree.read_text_file(file).is_ok();
+    let code = CODE_PROJECT_FILES.iter().any(exists);
+
+    if !code && DOCS_PROJECT_FILES.iter().any(exists) {
+        Some("docs")
+    } else if code || exists(&"package.json") {
+        Some("code")
+    } else {
+        None
+    }
+}
+
+impl Preset {
+    /// Applies the preset underneath the user's own settings: keys already present in the
+    /// server section win.
+    pub fn apply(&self, server: &mut ServerSettings) {
+        server
+            .diagnostic_severity
+            .get_or_insert_with(|| self.severity.to_string());
+
+        for (linter, enabled) in self.linters {
+            server.linters.entry(linter.to_string()).or_insert(*enabled);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use zed_extension_api::{
+    self as zed,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    serde_json::{self, Value},
+};
+
+/// The last latest-release lookup, with the validators GitHub returned for it.
+pub const RELEASE_CACHE_FILE: &str = "latest-release.json";
+
+#[derive(Serialize, Deserialize)]
+struct CachedRelease {
+    repo: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    version: String,
+    /// Asset names and download URLs.
+    assets: Vec<(String, String)>,
+}
+
+impl CachedRelease {
+    fn release(&self) -> zed::GithubRelease {
+        zed::GithubRelease {
+            version: self.version.clone(),
+            assets: self
+                .assets
+                .iter()
+                .map(|(name, download_url)| zed::GithubReleaseAsset {
+                    name: name.clone(),
+                    download_url: download_url.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Looks up the latest release of `repo` with a conditional request, answering from the
+/// cache when GitHub reports it unchanged. Unconditional lookups count against GitHub's
+/// rate limit, which is easily exhausted on shared IPs; "not modified" answers do not.
+///
+/// Returns `None` when the API cannot be used, leaving the lookup to Zed. A changed
+/// release is written to the cache only when `store` is set.
+pub fn latest(repo: &str, store: bool) -> Option<zed::GithubRelease> {
+    let cached = fs::read_to_string(RELEASE_CACHE_FILE)
+        .ok()
+        .and_then(|text| serde_json::from_str::<CachedRelease>(&text).ok())
+        .filter(|cached| cached.repo == repo);
+
+    let mut request = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(format!(
+            "https://api.github.com/repos/{repo}/releases/latest"
+        ))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "zed-harper")
+        .redirect_policy(RedirectPolicy::FollowAll);
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+    }
+
+    let response = match request.build().ok()?.fetch() {
+        Ok(response) => response,
+        // Zed reports responses other than 2xx as errors.
+        Err(e) if e.contains("304") => return cached.map(|cached| cached.release()),
+        Err(_) => return None,
+    };
+    if response.body.is_empty() {
+        return cached.map(|cached| cached.release());
+    }
+
+    let release: Value = serde_json::from_slice(&response.body).ok()?;
+    let assets: Vec<(String, String)> = release
+        .get("assets")?
+        .as_array()?
+        .iter()
+        .filter_map(|asset| {
+            let name = asset.get("name")?.as_str()?;
+            let url = asset.get("browser_download_url")?.as_str()?;
+            Some((name.to_string(), url.to_string()))
+        })
+        .collect();
+    if assets.is_empty() {
+        return None;
+    }
+
+    let header = |name: &str| {
+        response
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    };
+    let cached = CachedRelease {
+        repo: repo.to_string(),
+        etag: header("etag"),
+        last_modified: header("last-modified"),
+        version: release.get("tag_name")?.as_str()?.to_string(),
+        assets,
+    };
+    if store && let Ok(json) = serde_json::to_string(&cached) {
+        fs::write(RELEASE_CACHE_FILE, json).ok();
+    }
+
+    Some(cached.release())
+}
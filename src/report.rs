@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+
+/// A lint found by `harper-cli lint`.
+pub struct Finding {
+    pub rule: String,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+}
+
+/// Findings grouped by rule, then by file.
+type Grouped<'a> = BTreeMap<&'a str, BTreeMap<&'a str, Vec<&'a Finding>>>;
+
+/// Reads findings from harper-cli's terminal output. Each lint is a header such as
+/// `[SpellCheck] Error: Did you mean …` followed by a `╭─[file:line:column]` frame. Unindented
+/// lines between the two continue the message; other lines, colours included, are skipped.
+pub fn parse(output: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut header: Option<(String, String)> = None;
+
+    for line in output.lines().map(strip_ansi) {
+        if let Some((_, location)) = line.split_once("─[") {
+            let location = location.trim_end().trim_end_matches(']');
+            let mut parts = location.rsplitn(3, ':');
+            let (Some(_column), Some(line), Some(file)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            if let (Some((rule, message)), Ok(line)) = (header.take(), line.parse()) {
+                findings.push(Finding {
+                    rule,
+                    file: file.trim().to_string(),
+                    line,
+                    message,
+                });
+            }
+        } else if let Some(parsed) = parse_header(&line) {
+            header = Some(parsed);
+        } else if let Some((_, message)) = header.as_mut()
+            && !line.trim().is_empty()
+            && !line.starts_with(char::is_whitespace)
+        {
+            message.push(' ');
+            message.push_str(line.trim());
+        }
+    }
+
+    findings
+}
+
+/// Splits a header into rule and message. Headers without a `[Rule]` code are grouped
+/// under their kind, e.g. `Warning`.
+fn parse_header(line: &str) -> Option<(String, String)> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let (rule, rest) = match line.strip_prefix('[') {
+        Some(rest) => rest.split_once("] ")?,
+        None => ("", line),
+    };
+    let (kind, message) = rest.split_once(": ")?;
+    if !["Error", "Warning", "Advice"].contains(&kind) {
+        return None;
+    }
+    let rule = if rule.is_empty() { kind } else { rule };
+    Some((rule.to_string(), message.trim().to_string()))
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+fn group(findings: &[Finding]) -> Grouped<'_> {
+    let mut grouped = Grouped::new();
+    for finding in findings {
+        grouped
+            .entry(&finding.rule)
+            .or_default()
+            .entry(&finding.file)
+            .or_default()
+            .push(finding);
+    }
+    grouped
+}
+
+fn summary(findings: &[Finding]) -> String {
+    let mut files: Vec<&str> = findings.iter().map(|f| f.file.as_str()).collect();
+    files.sort_unstable();
+    files.dedup();
+    format!("{} findings in {} files", findings.len(), files.len())
+}
+
+/// Renders a Markdown report with a section per rule, most frequent first.
+pub fn markdown(findings: &[Finding]) -> String {
+    let mut report = format!("# Harper report\n\n{}.\n", summary(findings));
+
+    for (rule, files) in by_frequency(group(findings)) {
+        let count: usize = files.values().map(Vec::len).sum();
+        report.push_str(&format!("\n## {rule} ({count})\n\n"));
+        for (file, findings) in files {
+            for finding in findings {
+                report.push_str(&format!(
+                    "- `{file}:{}` — {}\n",
+                    finding.line, finding.message
+                ));
+            }
+        }
+    }
+
+    report
+}
+
+/// Renders a self-contained HTML page with the same layout as [`markdown`].
+pub fn html(findings: &[Finding]) -> String {
+    let mut report = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Harper report</title></head>\n<body>\n<h1>Harper report</h1>\n<p>{}.</p>\n",
+        summary(findings)
+    );
+
+    for (rule, files) in by_frequency(group(findings)) {
+        let count: usize = files.values().map(Vec::len).sum();
+        report.push_str(&format!("<h2>{} ({count})</h2>\n", escape(rule)));
+        for (file, findings) in files {
+            report.push_str(&format!("<h3>{}</h3>\n<ul>\n", escape(file)));
+            for finding in findings {
+                report.push_str(&format!(
+                    "<li>Line {}: {}</li>\n",
+                    finding.line,
+                    escape(&finding.message)
+                ));
+            }
+            report.push_str("</ul>\n");
+        }
+    }
+
+    report.push_str("</body>\n</html>\n");
+    report
+}
+
+fn by_frequency(grouped: Grouped<'_>) -> Vec<(&str, BTreeMap<&str, Vec<&Finding>>)> {
+    let mut rules: Vec<_> = grouped.into_iter().collect();
+    rules.sort_by_key(|(_, files)| std::cmp::Reverse(files.values().map(Vec::len).sum::<usize>()));
+    rules
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(rule: &str, file: &str, line: u32, message: &str) -> Finding {
+        Finding {
+            rule: rule.to_string(),
+            file: file.to_string(),
+            line,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_reads_well_formed_lints() {
+        let output = "\
+\u{1b}[31m[SpellCheck] Error:\u{1b}[0m Did you mean “there”?
+   ╭─[docs/intro.md:3:7]
+   │
+ 3 │ Look over thier.
+───╯
+Warning: Sentences should not end in a space.
+   ╭─[README.md:12:1]
+───╯
+";
+        let findings = parse(output);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].rule, "SpellCheck");
+        assert_eq!(findings[0].file, "docs/intro.md");
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(findings[0].message, "Did you mean “there”?");
+        assert_eq!(findings[1].rule, "Warning");
+        assert_eq!(findings[1].file, "README.md");
+        assert_eq!(findings[1].line, 12);
+    }
+
+    #[test]
+    fn parse_joins_multi_line_messages() {
+        let output = "\
+[LongSentences] Advice: This sentence is long.
+Consider splitting it.
+   ╭─[notes.md:1:1]
+───╯
+";
+        let findings = parse(output);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "This sentence is long. Consider splitting it."
+        );
+    }
+
+    #[test]
+    fn parse_keeps_colons_in_paths() {
+        let output = "\
+[SpellCheck] Error: Did you mean “the”?
+   ╭─[C:\\docs\\a:b.md:4:2]
+───╯
+";
+        let findings = parse(output);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, "C:\\docs\\a:b.md");
+        assert_eq!(findings[0].line, 4);
+    }
+
+    #[test]
+    fn parse_handles_empty_output() {
+        assert!(parse("").is_empty());
+        assert_eq!(markdown(&[]), "# Harper report\n\n0 findings in 0 files.\n");
+    }
+
+    #[test]
+    fn html_escapes_messages_and_paths() {
+        let findings = [finding(
+            "SpellCheck",
+            "docs/<a&b>.md",
+            1,
+            "Did you mean \"<br>\"?",
+        )];
+        let report = html(&findings);
+        assert!(report.contains("<h3>docs/&lt;a&amp;b&gt;.md</h3>"));
+        assert!(report.contains("<li>Line 1: Did you mean &quot;&lt;br&gt;&quot;?</li>"));
+        assert!(!report.contains("<br>"));
+    }
+}
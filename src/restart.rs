@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Number of consecutive crashes after which the previously installed version is used.
+pub const FALLBACK_AFTER: u32 = 3;
+/// Number of consecutive crashes after which the server is launched with the default
+/// configuration. Never reached on Windows, where no exit status is recorded.
+pub const SAFE_MODE_AFTER: u32 = 4;
+/// Longest delay before relaunching a crashed server, in seconds.
+const MAX_BACKOFF_SECS: u32 = 30;
+
+/// Counts consecutive crashes of a worktree's server. The extension does not see the
+/// server exit, so only exit statuses recorded by the launch wrapper are evidence; a
+/// relaunch without one, such as a manual restart, is never counted as a crash.
+///
+/// The count is atomic so `/harper reset-crashes`, which only gets `&self`, can clear it.
+#[derive(Default)]
+pub struct RestartTracker {
+    crashes: AtomicU32,
+}
+
+impl RestartTracker {
+    /// Records a launch, given the exit status of the previous run if one was recorded,
+    /// and returns the number of consecutive crashes so far.
+    pub fn register_launch(&self, previous_exit: Option<i32>) -> u32 {
+        let crashes = match previous_exit {
+            Some(code) if is_crash(code) => self.crashes() + 1,
+            _ => 0,
+        };
+        self.crashes.store(crashes, Ordering::Relaxed);
+        crashes
+    }
+
+    pub fn crashes(&self) -> u32 {
+        self.crashes.load(Ordering::Relaxed)
+    }
+
+    /// Forgets earlier crashes, leaving safe mode and the fallback version at the next launch.
+    pub fn reset(&self) {
+        self.crashes.store(0, Ordering::Relaxed);
+    }
+
+    /// Seconds to wait before launching the server: none after a normal exit, then
+    /// doubling with each consecutive crash up to [`MAX_BACKOFF_SECS`].
+    pub fn backoff_secs(&self) -> u32 {
+        match self.crashes() {
+            0 => 0,
+            crashes => 2u32.saturating_pow(crashes - 1).min(MAX_BACKOFF_SECS),
+        }
+    }
+
+    /// Whether the server runs with the default configuration after repeated crashes.
+    pub fn in_safe_mode(&self) -> bool {
+        self.crashes() >= SAFE_MODE_AFTER
+    }
+}
+
+/// Whether an exit status is a crash: anything but success and the signals the server is
+/// stopped with on purpose (SIGHUP, SIGINT, SIGKILL and SIGTERM, as reported by `sh`).
+fn is_crash(code: i32) -> bool {
+    !matches!(code, 0 | 129 | 130 | 137 | 143)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_recorded_crashes_count() {
+        let restarts = RestartTracker::default();
+        assert_eq!(restarts.register_launch(None), 0);
+        assert_eq!(restarts.register_launch(Some(101)), 1);
+        assert_eq!(restarts.register_launch(Some(139)), 2);
+        assert_eq!(restarts.register_launch(Some(143)), 0);
+        assert_eq!(restarts.register_launch(Some(1)), 1);
+        assert_eq!(restarts.register_launch(None), 0);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let restarts = RestartTracker::default();
+        let mut delays = vec![restarts.backoff_secs()];
+        for _ in 0..7 {
+            restarts.register_launch(Some(1));
+            delays.push(restarts.backoff_secs());
+        }
+        assert_eq!(delays, [0, 1, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn safe_mode_follows_the_crash_count() {
+        let restarts = RestartTracker::default();
+        for _ in 0..SAFE_MODE_AFTER {
+            restarts.register_launch(Some(1));
+        }
+        assert!(restarts.in_safe_mode());
+        restarts.reset();
+        assert!(!restarts.in_safe_mode());
+        assert_eq!(restarts.backoff_secs(), 0);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::env_var;
+use zed_extension_api as zed;
+
+/// A desktop sandbox Zed may run in, confining which paths harper-ls can access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+pub struct SandboxDirs {
+    pub config: String,
+    pub data: String,
+}
+
+impl Sandbox {
+    pub fn detect(env: &[(String, String)]) -> Option<Self> {
+        if env_var(env, "FLATPAK_ID").is_some() || env_var(env, "container") == Some("flatpak") {
+            Some(Self::Flatpak)
+        } else if env_var(env, "SNAP").is_some() {
+            Some(Self::Snap)
+        } else {
+            None
+        }
+    }
+
+    /// Configuration and data directories inside the sandbox that harper-ls can write to.
+    pub fn dirs(self, env: &[(String, String)]) -> Option<SandboxDirs> {
+        match self {
+            Self::Flatpak => Some(SandboxDirs {
+                config: env_var(env, "XDG_CONFIG_HOME")?.to_string(),
+                data: env_var(env, "XDG_DATA_HOME")?.to_string(),
+            }),
+            Self::Snap => {
+                let user_data = env_var(env, "SNAP_USER_DATA")?;
+                Some(SandboxDirs {
+                    config: format!("{user_data}/.config"),
+                    data: format!("{user_data}/.local/share"),
+                })
+            }
+        }
+    }
+
+    /// Returns whether `path` lies within a directory the sandbox can read and write.
+    pub fn can_access(
+        self,
+        env: &[(String, String)],
+        worktree: &zed::Worktree,
+        path: &str,
+    ) -> bool {
+        let mut roots = vec![worktree.root_path()];
+        if let Some(dirs) = self.dirs(env) {
+            roots.extend([dirs.config, dirs.data]);
+        }
+        roots.push("/tmp".to_string());
+
+        roots
+            .iter()
+            .any(|root| path.starts_with(root.trim_end_matches('/')))
+    }
+}
@@ -0,0 +1,149 @@
+use crate::config::DIALECTS;
+use crate::{BinarySource, NAME, linters, presets};
+use zed_extension_api::serde_json::{Map, Value, json};
+
+/// Suggested location of the schema printed by `/harper schema`, relative to the worktree.
+pub const SCHEMA_FILE: &str = ".zed/harper-ls.schema.json";
+
+const SEVERITIES: &[&str] = &["error", "warning", "information", "hint"];
+
+/// Returns a JSON schema for Zed settings files covering the `lsp.harper-ls` section:
+/// the binary, the extension options and the `harper-ls` server settings.
+pub fn settings_schema() -> Value {
+    let linters: Map<String, Value> = linters::KNOWN
+        .iter()
+        .map(|linter| (linter.to_string(), json!({ "type": "boolean" })))
+        .collect();
+    let presets: Vec<&str> = presets::PRESETS.iter().map(|preset| preset.name).collect();
+    let enforced_dialects: Vec<&str> = std::iter::once("auto")
+        .chain(DIALECTS.iter().copied())
+        .collect();
+    let string_list = json!({ "type": "array", "items": { "type": "string" } });
+
+    let server = json!({
+        "type": "object",
+        "properties": {
+            "userDictPath": { "type": "string" },
+            "fileDictPath": { "type": "string" },
+            "workspaceDictPath": { "type": "string" },
+            "ignoredLintsPath": { "type": "string" },
+            "diagnosticSeverity": { "enum": SEVERITIES },
+            "dialect": { "enum": DIALECTS },
+            "isolateEnglish": { "type": "boolean" },
+            "excludePatterns": string_list,
+            "codeActions": {
+                "type": "object",
+                "properties": { "ForceStable": { "type": "boolean" } }
+            },
+            "markdown": {
+                "type": "object",
+                "properties": { "IgnoreLinkTitle": { "type": "boolean" } }
+            },
+            "linters": {
+                "type": "object",
+                "properties": linters,
+                "additionalProperties": { "type": "boolean" }
+            }
+        }
+    });
+
+    let settings = json!({
+        "type": "object",
+        "properties": {
+            NAME: server,
+            "enabled": { "type": "boolean", "default": true },
+            "version": { "type": "string", "description": "Release to pin, e.g. v0.36.0" },
+            "expected_sha256": {
+                "type": "string",
+                "pattern": "^(sha256:)?[0-9a-fA-F]{64}$",
+                "description": "SHA-256 of the harper-ls binary of the pinned version"
+            },
+            "repository": { "type": "string", "pattern": "^[^/]+/[^/]+$" },
+            "mirrors": {
+                "type": "array",
+                "items": { "type": "string" }
+            },
+            "preset": { "enum": presets },
+            "project_presets": {
+                "type": "object",
+                "properties": {
+                    "docs": { "enum": presets },
+                    "code": { "enum": presets }
+                },
+                "additionalProperties": false,
+                "default": { "docs": "technical-docs" }
+            },
+            "mode": { "enum": ["full", "spelling", "grammar"] },
+            "enforce_dialect": { "enum": enforced_dialects },
+            "exclude": string_list,
+            "exclude_vendored": { "type": "boolean", "default": true },
+            "exclude_generated": { "type": "boolean", "default": true },
+            "overrides": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string" },
+                        "enabled": { "type": "boolean" }
+                    },
+                    "required": ["path"]
+                }
+            },
+            "devcontainer": { "type": "boolean", "default": false },
+            "resolution": {
+                "type": "array",
+                "items": { "enum": BinarySource::ALL.map(BinarySource::name) }
+            },
+            "working_directory": { "type": "string" },
+            "wrapper": {
+                "type": "array",
+                "items": { "type": "string" }
+            },
+            "environment": {
+                "type": "array",
+                "items": { "type": "string" }
+            },
+            "log_level": { "enum": ["error", "warn", "info", "debug", "trace"] },
+            "update_check": { "enum": ["worktree", "session", "daily"], "default": "worktree" },
+            "binary_share_dir": { "type": "string" },
+            "glossary": { "type": "string" },
+            "failure_report": { "type": "boolean", "default": false },
+            "stderr_log": { "type": "boolean", "default": false },
+            "suppress": {
+                "type": "array",
+                // Newer harper-ls releases add rules, so known names are only suggestions.
+                "items": { "type": "string", "examples": linters::KNOWN }
+            },
+            "severities": {
+                "type": "object",
+                "additionalProperties": { "enum": ["off", "error", "warning", "information", "hint"] }
+            }
+        }
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Harper settings for Zed",
+        "type": "object",
+        "properties": {
+            "lsp": {
+                "type": "object",
+                "properties": {
+                    NAME: {
+                        "type": "object",
+                        "properties": {
+                            "binary": {
+                                "type": "object",
+                                "properties": {
+                                    "path": { "type": "string" },
+                                    "arguments": string_list
+                                }
+                            },
+                            "settings": settings
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
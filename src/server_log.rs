@@ -0,0 +1,73 @@
+use std::fs;
+
+/// The server's stderr in the extension directory, kept when `stderr_log` is on.
+pub const STDERR_LOG: &str = "harper-ls.stderr.log";
+/// Size after which the log is rotated when the server is next launched.
+const MAX_BYTES: u64 = 1024 * 1024;
+/// Number of rotated logs kept next to the current one.
+const ROTATED: u32 = 3;
+/// Lines of the log shown by `/harper diagnose`.
+const TAIL_LINES: usize = 20;
+
+/// Moves a log that outgrew [`MAX_BYTES`] to `.1`, shifting older ones up and dropping
+/// the oldest. Only called at launch, since the extension cannot see the running server.
+pub fn rotate() {
+    if !fs::metadata(STDERR_LOG).is_ok_and(|log| log.len() > MAX_BYTES) {
+        return;
+    }
+    for n in (1..ROTATED).rev() {
+        fs::rename(
+            format!("{STDERR_LOG}.{n}"),
+            format!("{STDERR_LOG}.{}", n + 1),
+        )
+        .ok();
+    }
+    fs::rename(STDERR_LOG, format!("{STDERR_LOG}.1")).ok();
+}
+
+/// Prefix of the files [`wrap`] records server exit statuses in.
+pub const EXIT_FILE_PREFIX: &str = "last-exit-";
+
+/// File [`wrap`] records a worktree's server exit status in, for crash detection.
+pub fn exit_file(worktree_id: u64) -> String {
+    format!("{EXIT_FILE_PREFIX}{worktree_id}")
+}
+
+/// Reads and removes the exit status recorded for the worktree's previous server. There
+/// is none when the server is still running, was killed, or ran without the wrapper.
+pub fn take_exit_status(worktree_id: u64) -> Option<i32> {
+    let file = exit_file(worktree_id);
+    let status = fs::read_to_string(&file).ok()?;
+    fs::remove_file(&file).ok();
+    status.trim().parse().ok()
+}
+
+/// Launches `command` through `/bin/sh` after waiting `delay_secs`, writing its exit
+/// status to `status` and, if given, appending its stderr to `log`; both are absolute
+/// paths. The shell waits for the server instead of `exec`ing it; if the shell is
+/// killed, the server still exits when its stdin closes.
+pub fn wrap(
+    command: String,
+    args: Vec<String>,
+    status: &str,
+    log: Option<&str>,
+    delay_secs: u32,
+) -> (String, Vec<String>) {
+    let script = r#"status=$1; log=$2; sleep "$3"; shift 3; if [ -n "$log" ]; then "$@" 2>>"$log"; else "$@"; fi; code=$?; echo "$code" >"$status"; exit "$code""#;
+    let delay = delay_secs.to_string();
+    let args = ["-c", script, "sh", status, log.unwrap_or_default(), &delay]
+        .into_iter()
+        .map(str::to_string)
+        .chain(std::iter::once(command))
+        .chain(args)
+        .collect();
+    ("/bin/sh".to_string(), args)
+}
+
+/// The last lines of the current log, if the server has written to it.
+pub fn tail() -> Option<String> {
+    let log = fs::read_to_string(STDERR_LOG).ok()?;
+    let lines: Vec<&str> = log.lines().collect();
+    let tail = lines[lines.len().saturating_sub(TAIL_LINES)..].join("\n");
+    (!tail.is_empty()).then_some(tail)
+}
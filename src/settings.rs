@@ -0,0 +1,298 @@
+use crate::error::ConfigError;
+use crate::{NAME, presets};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use zed_extension_api::{
+    self as zed,
+    serde_json::{self, Map, Value},
+    settings::LspSettings,
+};
+
+/// Top-level keys of the `harper-ls` server section, recognized when written without it.
+const SERVER_SETTINGS: &[&str] = &[
+    "userDictPath",
+    "fileDictPath",
+    "workspaceDictPath",
+    "ignoredLintsPath",
+    "diagnosticSeverity",
+    "dialect",
+    "isolateEnglish",
+    "excludePatterns",
+    "codeActions",
+    "markdown",
+    "linters",
+];
+
+/// First harper-ls release reading linter names in PascalCase; older releases expect
+/// them in snake_case (`spell_check` rather than `SpellCheck`).
+const PASCAL_CASE_LINTERS_SINCE: &[u64] = &[0, 15, 0];
+
+/// Alternative spellings of the server section name.
+const SECTION_ALIASES: &[&str] = &["harper_ls", "harper"];
+
+/// The user's `lsp.harper-ls.settings`: options read by the extension at the top level,
+/// and the `harper-ls` section for the server.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct HarperSettings {
+    #[serde(rename = "harper-ls")]
+    pub server: ServerSettings,
+    #[serde(flatten)]
+    pub options: ExtensionOptions,
+    /// Other top-level keys, forwarded to the server unchanged.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Options read by the extension itself; they are not sent to the server.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ExtensionOptions {
+    pub enabled: bool,
+    pub version: Option<String>,
+    pub expected_sha256: Option<String>,
+    pub repository: Option<String>,
+    pub mirrors: Vec<String>,
+    pub preset: Option<String>,
+    pub project_presets: BTreeMap<String, String>,
+    pub mode: Option<String>,
+    pub enforce_dialect: Option<String>,
+    pub exclude: Vec<String>,
+    pub exclude_vendored: bool,
+    pub exclude_generated: bool,
+    pub overrides: Vec<Override>,
+    pub devcontainer: bool,
+    pub resolution: Vec<String>,
+    pub update_check: Option<String>,
+    pub environment: Option<Vec<String>>,
+    pub log_level: Option<String>,
+    pub wrapper: Vec<String>,
+    pub working_directory: Option<String>,
+    pub binary_share_dir: Option<String>,
+    pub glossary: Option<String>,
+    pub severities: BTreeMap<String, String>,
+    pub suppress: Vec<String>,
+    pub failure_report: bool,
+    pub stderr_log: bool,
+}
+
+impl Default for ExtensionOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            version: None,
+            expected_sha256: None,
+            repository: None,
+            mirrors: Vec::new(),
+            preset: None,
+            project_presets: presets::PROJECT_PRESETS
+                .iter()
+                .map(|(kind, preset)| (kind.to_string(), preset.to_string()))
+                .collect(),
+            mode: None,
+            enforce_dialect: None,
+            exclude: Vec::new(),
+            exclude_vendored: true,
+            exclude_generated: true,
+            overrides: Vec::new(),
+            devcontainer: false,
+            resolution: Vec::new(),
+            update_check: None,
+            environment: None,
+            log_level: None,
+            wrapper: Vec::new(),
+            working_directory: None,
+            binary_share_dir: None,
+            glossary: None,
+            severities: BTreeMap::new(),
+            suppress: Vec::new(),
+            failure_report: false,
+            stderr_log: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Override {
+    pub path: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// The `harper-ls` section, as the server reads it.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ServerSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_dict_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_dict_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_dict_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignored_lints_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostic_severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isolate_english: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_patterns: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_actions: Option<CodeActions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<MarkdownOptions>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub linters: BTreeMap<String, bool>,
+    /// Settings the extension does not know, forwarded unchanged.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct CodeActions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_stable: Option<bool>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct MarkdownOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_link_title: Option<bool>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl HarperSettings {
+    /// Reads and validates the worktree's settings. Misplaced server settings are moved
+    /// into the section, with a warning.
+    pub fn for_worktree(
+        worktree: &zed::Worktree,
+        warnings: &mut Vec<String>,
+    ) -> Result<Self, ConfigError> {
+        let settings = LspSettings::for_worktree(NAME, worktree)
+            .map_err(ConfigError::InvalidSettings)?
+            .settings;
+
+        match settings {
+            Some(settings) => Self::from_value(settings, warnings),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn from_value(mut settings: Value, warnings: &mut Vec<String>) -> Result<Self, ConfigError> {
+        if let Some(root) = settings.as_object_mut() {
+            normalize_section(root, warnings);
+        }
+
+        serde_json::from_value(settings).map_err(|e| ConfigError::InvalidSettings(e.to_string()))
+    }
+}
+
+impl ServerSettings {
+    /// Settings holding file system paths, by name.
+    pub fn paths_mut(&mut self) -> [(&'static str, &mut Option<String>); 4] {
+        [
+            ("userDictPath", &mut self.user_dict_path),
+            ("fileDictPath", &mut self.file_dict_path),
+            ("workspaceDictPath", &mut self.workspace_dict_path),
+            ("ignoredLintsPath", &mut self.ignored_lints_path),
+        ]
+    }
+
+    /// Serializes the section with the key names understood by `server_version`,
+    /// or by the latest release when it is unknown.
+    pub fn into_value(self, server_version: Option<&str>) -> Result<Value, ConfigError> {
+        let legacy_linters = server_version
+            .and_then(crate::parse_version)
+            .is_some_and(|version| version.as_slice() < PASCAL_CASE_LINTERS_SINCE);
+
+        let mut value =
+            serde_json::to_value(self).map_err(|e| ConfigError::InvalidSettings(e.to_string()))?;
+        if legacy_linters && let Some(Value::Object(linters)) = value.get_mut("linters") {
+            *linters = std::mem::take(linters)
+                .into_iter()
+                .map(|(name, enabled)| (snake_case(&name), enabled))
+                .collect();
+        }
+        Ok(value)
+    }
+
+    /// Appends exclusion globs, skipping ones already present.
+    pub fn exclude<S: AsRef<str>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            if !self.exclude_patterns.iter().any(|known| known == pattern) {
+                self.exclude_patterns.push(pattern.to_string());
+            }
+        }
+    }
+}
+
+/// Moves server settings written under an alias of the section name, or without the
+/// section, into the `harper-ls` section. Keys already in the section take precedence.
+fn normalize_section(root: &mut Map<String, Value>, warnings: &mut Vec<String>) {
+    let mut moved = Map::new();
+    for alias in SECTION_ALIASES {
+        if let Some(Value::Object(section)) = root.remove(*alias) {
+            warnings.push(format!(
+                "Settings under `{alias}` were applied as `{NAME}`; rename the section to `{NAME}`"
+            ));
+            moved.extend(section);
+        }
+    }
+    for key in SERVER_SETTINGS {
+        if let Some(value) = root.remove(*key) {
+            warnings.push(format!(
+                "`{key}` was moved into the `{NAME}` section, where harper-ls reads it"
+            ));
+            moved.insert(key.to_string(), value);
+        }
+    }
+    if moved.is_empty() {
+        return;
+    }
+
+    if let Some(section) = root
+        .entry(NAME)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+    {
+        for (key, value) in moved {
+            section.entry(key).or_insert(value);
+        }
+    }
+}
+
+/// Converts a PascalCase linter name to snake_case, e.g. `AnA` to `an_a`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case_splits_on_capitals() {
+        assert_eq!(snake_case("AnA"), "an_a");
+        assert_eq!(snake_case("SpellCheck"), "spell_check");
+        assert_eq!(snake_case("spell_check"), "spell_check");
+    }
+}
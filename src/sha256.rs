@@ -0,0 +1,71 @@
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Returns the SHA-256 digest of a file as lowercase hex.
+pub fn file_digest(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Checks a file against an expected digest, written in hex with an optional `sha256:`
+/// prefix. Returns the actual digest, or the read error, otherwise.
+pub fn verify(path: &Path, expected: &str) -> Result<(), String> {
+    let expected = expected.trim().trim_start_matches("sha256:");
+    match file_digest(path) {
+        Ok(digest) if digest.eq_ignore_ascii_case(expected) => Ok(()),
+        Ok(digest) => Err(digest),
+        Err(e) => Err(format!("unreadable ({e})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("zed-harper-{name}"));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn verify_accepts_the_digest_with_or_without_prefix() {
+        let path = file("sha256-match", b"abc");
+        assert_eq!(verify(&path, ABC), Ok(()));
+        assert_eq!(
+            verify(&path, &format!("sha256:{}", ABC.to_uppercase())),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_reports_the_actual_digest() {
+        let path = file("sha256-mismatch", b"abd");
+        let found = verify(&path, ABC).unwrap_err();
+        assert_eq!(found.len(), 64);
+        assert_ne!(found, ABC);
+    }
+
+    #[test]
+    fn verify_reports_unreadable_files() {
+        let missing = std::env::temp_dir().join("zed-harper-sha256-missing");
+        assert!(verify(&missing, ABC).unwrap_err().starts_with("unreadable"));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::ResolveError;
+use std::path::{Path, PathBuf};
+use zed_extension_api as zed;
+
+/// Extensions Windows tries for a command without one, in `PATHEXT` order.
+const WINDOWS_EXTENSIONS: &[&str] = &["exe", "cmd", "bat"];
+
+/// Returns whether a `PATH` lookup landed on an asdf or mise shim.
+pub fn is_shim(path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    path.contains("/shims/") && (path.contains("asdf") || path.contains("mise"))
+}
+
+/// Resolves a shim to the versioned binary it dispatches to, using the tool version
+/// pinned in the worktree's `.tool-versions` or `mise.toml`. Returns `None` when no
+/// version is pinned, and an error when the pinned version is not installed.
+pub fn resolve(
+    worktree: &zed::Worktree,
+    shim: &str,
+) -> Result<Option<(PathBuf, String)>, ResolveError> {
+    let shim_path = Path::new(shim);
+    let (Some(root), Some(file_name), Some((tool, version))) = (
+        shim_path.parent().and_then(Path::parent),
+        shim_path.file_name(),
+        tool_version(worktree),
+    ) else {
+        return Ok(None);
+    };
+
+    let path = root
+        .join("installs")
+        .join(tool.replace([':', '/'], "-"))
+        .join(&version)
+        .join("bin")
+        .join(file_name);
+
+    if worktree.which(&path.to_string_lossy()).is_none() {
+        return Err(ResolveError::ShimTargetMissing {
+            shim: shim.to_string(),
+            version,
+            target: path.display().to_string(),
+        });
+    }
+
+    Ok(Some((path, version)))
+}
+
+/// Finds the first Harper tool entry, e.g. `harper-ls 0.40.0` or `"cargo:harper-ls" = "0.40.0"`.
+fn tool_version(worktree: &zed::Worktree) -> Option<(String, String)> {
+    let tool_versions = || {
+        let text = worktree.read_text_file(".tool-versions").ok()?;
+        text.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let tool = parts.next()?;
+            let version = parts.next()?;
+            tool.contains("harper")
+                .then(|| (tool.to_string(), version.to_string()))
+        })
+    };
+
+    let mise_toml = || {
+        ["mise.toml", ".mise.toml"].iter().find_map(|file| {
+            let text = worktree.read_text_file(file).ok()?;
+            let mut in_tools = false;
+            text.lines().find_map(|line| {
+                let line = line.trim();
+                if line.starts_with('[') {
+                    in_tools = line == "[tools]";
+                    return None;
+                }
+                let (tool, version) = line.split_once('=')?;
+                let tool = tool.trim().trim_matches('"');
+                let version = version.trim().trim_matches('"');
+                (in_tools && tool.contains("harper"))
+                    .then(|| (tool.to_string(), version.to_string()))
+            })
+        })
+    };
+
+    tool_versions().or_else(mise_toml)
+}
+
+/// Looks up `program` on the worktree's PATH. On Windows, `which` may not apply `PATHEXT`,
+/// so the name is also tried with each executable extension.
+pub fn which(worktree: &zed::Worktree, program: &str, os: zed::Os) -> Option<String> {
+    worktree.which(program).or_else(|| {
+        (os == zed::Os::Windows && Path::new(program).extension().is_none())
+            .then(|| {
+                WINDOWS_EXTENSIONS
+                    .iter()
+                    .find_map(|extension| worktree.which(&format!("{program}.{extension}")))
+            })
+            .flatten()
+    })
+}
+
+/// Makes a Windows command directly launchable: a path without an extension gets `.exe`,
+/// and batch wrappers (npm, some Chocolatey packages) run through `cmd /C`, since they
+/// cannot be spawned themselves. Scoop shims are executables and need no change.
+pub fn windows_launcher(path: &mut PathBuf, args: &mut Vec<String>) {
+    match path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .as_deref()
+    {
+        None if path.components().count() > 1 => {
+            path.set_extension("exe");
+        }
+        Some("cmd" | "bat") => {
+            let script = std::mem::replace(path, PathBuf::from("cmd.exe"));
+            let mut wrapped = vec!["/C".to_string(), script.to_string_lossy().into_owned()];
+            wrapped.append(args);
+            *args = wrapped;
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,109 @@
+/// Prose statistics for a single document.
+#[derive(Default, Clone, Copy)]
+pub struct TextStats {
+    pub words: usize,
+    pub sentences: usize,
+    pub syllables: usize,
+}
+
+impl TextStats {
+    /// Collects statistics from Markdown or plain text, skipping fenced code blocks.
+    pub fn from_text(text: &str) -> Self {
+        let mut stats = Self::default();
+        let mut in_fence = false;
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                let word: String = token.chars().filter(|c| c.is_alphanumeric()).collect();
+                if word.is_empty() {
+                    continue;
+                }
+                stats.words += 1;
+                stats.syllables += syllables(&word);
+                if token.ends_with(['.', '!', '?']) {
+                    stats.sentences += 1;
+                }
+            }
+        }
+
+        if stats.words > 0 && stats.sentences == 0 {
+            stats.sentences = 1;
+        }
+        stats
+    }
+
+    pub fn add(&mut self, other: Self) {
+        self.words += other.words;
+        self.sentences += other.sentences;
+        self.syllables += other.syllables;
+    }
+
+    pub fn average_sentence_length(&self) -> f64 {
+        ratio(self.words, self.sentences)
+    }
+
+    /// Flesch reading ease: higher is easier, 60–70 is plain English.
+    pub fn reading_ease(&self) -> f64 {
+        206.835 - 1.015 * self.average_sentence_length() - 84.6 * ratio(self.syllables, self.words)
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 { 0.0 } else { a as f64 / b as f64 }
+}
+
+/// Estimates syllables by counting vowel groups, ignoring a silent trailing `e`.
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let is_vowel = |c: char| "aeiouy".contains(c);
+
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Renders a Markdown report with one row per file and an aggregate row.
+pub fn report(files: &[(String, TextStats)]) -> String {
+    let mut total = TextStats::default();
+    let mut report = String::from(
+        "| File | Words | Sentences | Avg. sentence length | Reading ease |\n| --- | ---: | ---: | ---: | ---: |\n",
+    );
+
+    for (file, stats) in files {
+        total.add(*stats);
+        report.push_str(&row(file, stats));
+    }
+    report.push_str(&row("**Total**", &total));
+
+    report
+}
+
+fn row(label: &str, stats: &TextStats) -> String {
+    format!(
+        "| {label} | {} | {} | {:.1} | {:.1} |\n",
+        stats.words,
+        stats.sentences,
+        stats.average_sentence_length(),
+        stats.reading_ease()
+    )
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time of the last successful release check, in seconds since the epoch, kept in the
+/// extension directory so it survives Zed restarts.
+pub const LAST_CHECK_FILE: &str = "last-update-check";
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often the managed install looks for a newer release.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cadence {
+    /// Whenever a worktree starts the server without a binary resolved for it.
+    Worktree,
+    /// Once per Zed session.
+    Session,
+    /// Once a day, across sessions.
+    Daily,
+}
+
+impl Cadence {
+    /// Parses the `update_check` option, warning about unknown values.
+    pub fn parse(name: Option<&str>, warnings: &mut Vec<String>) -> Self {
+        match name {
+            None | Some("worktree") => Self::Worktree,
+            Some("session") => Self::Session,
+            Some("daily") => Self::Daily,
+            Some(name) => {
+                warnings.push(format!(
+                    "Unknown update_check {name:?}; expected worktree, session or daily"
+                ));
+                Self::Worktree
+            }
+        }
+    }
+}
+
+/// Returns whether a release check ran within the last day.
+pub fn checked_today() -> bool {
+    fs::read_to_string(LAST_CHECK_FILE)
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .is_some_and(|secs| now().saturating_sub(Duration::from_secs(secs)) < DAY)
+}
+
+/// Records that a release check just ran.
+pub fn record_check() {
+    if let Err(e) = fs::write(LAST_CHECK_FILE, now().as_secs().to_string()) {
+        eprintln!("harper-ls: failed to record update check: {e}");
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
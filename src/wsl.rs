@@ -0,0 +1,101 @@
+use crate::platform::Platform;
+use crate::{BinarySource, HarperBinary, server_args};
+use std::path::PathBuf;
+use zed_extension_api as zed;
+
+const UNC_PREFIXES: &[&str] = &[r"\\wsl$\", r"\\wsl.localhost\"];
+/// Directories of the distribution searched for `harper-ls`. The extension cannot run
+/// anything inside the distribution to read its `PATH`, so it probes these through the
+/// `\\wsl$` share instead.
+const SEARCH_DIRS: &[&str] = &[
+    "/usr/local/bin",
+    "/usr/bin",
+    "/bin",
+    "/home/linuxbrew/.linuxbrew/bin",
+    "/nix/var/nix/profiles/default/bin",
+];
+
+/// Returns the WSL distribution a worktree opened by Zed on Windows lives in.
+pub fn distro(worktree: &zed::Worktree) -> Option<String> {
+    if Platform::current().os != zed::Os::Windows {
+        return None;
+    }
+
+    let root = worktree.root_path();
+    let rest = UNC_PREFIXES
+        .iter()
+        .find_map(|prefix| strip_prefix_ignore_case(&root, prefix))?;
+    rest.split('\\')
+        .next()
+        .filter(|distro| !distro.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns the Linux path of `program` in one of [`SEARCH_DIRS`] of the distribution the
+/// worktree lives in, if it is there.
+pub fn find(worktree: &zed::Worktree, distro: &str, program: &str) -> Option<String> {
+    let root = worktree.root_path();
+    let prefix = UNC_PREFIXES
+        .iter()
+        .find(|prefix| strip_prefix_ignore_case(&root, prefix).is_some())?;
+    SEARCH_DIRS.iter().find_map(|dir| {
+        let share_path = format!(r"{prefix}{distro}{}\{program}", dir.replace('/', r"\"));
+        worktree
+            .which(&share_path)
+            .map(|_| format!("{dir}/{program}"))
+    })
+}
+
+/// Translates a Windows path (`\\wsl$\<distro>\...` or `C:\...`) into the path seen inside
+/// the distribution. Paths that are already Linux-style are returned unchanged.
+pub fn to_linux_path(path: &str, distro: &str) -> String {
+    if path.starts_with('/') {
+        return path.to_string();
+    }
+
+    for prefix in UNC_PREFIXES {
+        if let Some(rest) = strip_prefix_ignore_case(path, prefix)
+            && let Some(rest) = strip_prefix_ignore_case(rest, distro)
+        {
+            let rest = rest.replace('\\', "/");
+            return if rest.is_empty() {
+                "/".to_string()
+            } else {
+                rest
+            };
+        }
+    }
+
+    let mut chars = path.chars();
+    if let (Some(drive), Some(':')) = (chars.next(), chars.next())
+        && drive.is_ascii_alphabetic()
+    {
+        let rest = chars.as_str().replace('\\', "/");
+        return format!("/mnt/{}{rest}", drive.to_ascii_lowercase());
+    }
+
+    path.to_string()
+}
+
+/// Launches `program` inside the distribution through `wsl.exe`.
+pub fn binary(distro: &str, program: &str, user_args: Option<Vec<String>>) -> HarperBinary {
+    let args = ["-d", distro, "-e", program]
+        .into_iter()
+        .map(str::to_string)
+        .chain(server_args(user_args))
+        .collect();
+
+    HarperBinary {
+        path: PathBuf::from("wsl.exe"),
+        args,
+        env: None,
+        source: BinarySource::Wsl,
+        version: None,
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = value.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &value[prefix.len()..])
+}